@@ -0,0 +1,312 @@
+-- Pagila sample schema, as expected by the handlers in src/routes.
+-- Databases that were loaded from the Pagila dump before migrations existed
+-- get this version recorded as applied by `--migrate` instead of running it.
+
+CREATE TYPE mpaa_rating AS ENUM ('G', 'PG', 'PG-13', 'R', 'NC-17');
+
+CREATE DOMAIN year AS integer
+    CONSTRAINT year_check CHECK (VALUE >= 1901 AND VALUE <= 2155);
+
+CREATE FUNCTION last_updated() RETURNS trigger
+    LANGUAGE plpgsql
+AS $$
+BEGIN
+    NEW.last_update = CURRENT_TIMESTAMP;
+    RETURN NEW;
+END $$;
+
+CREATE TABLE actor (
+    actor_id    serial PRIMARY KEY,
+    first_name  varchar(45) NOT NULL,
+    last_name   varchar(45) NOT NULL,
+    last_update timestamp NOT NULL DEFAULT now()
+);
+CREATE INDEX idx_actor_last_name ON actor (last_name);
+
+CREATE TABLE category (
+    category_id serial PRIMARY KEY,
+    name        varchar(25) NOT NULL,
+    last_update timestamp NOT NULL DEFAULT now()
+);
+
+CREATE TABLE country (
+    country_id  serial PRIMARY KEY,
+    country     varchar(50) NOT NULL,
+    last_update timestamp NOT NULL DEFAULT now()
+);
+
+CREATE TABLE city (
+    city_id     serial PRIMARY KEY,
+    city        varchar(50) NOT NULL,
+    country_id  smallint NOT NULL REFERENCES country (country_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    last_update timestamp NOT NULL DEFAULT now()
+);
+CREATE INDEX idx_fk_country_id ON city (country_id);
+
+CREATE TABLE address (
+    address_id  serial PRIMARY KEY,
+    address     varchar(50) NOT NULL,
+    address2    varchar(50),
+    district    varchar(20) NOT NULL,
+    city_id     smallint NOT NULL REFERENCES city (city_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    postal_code varchar(10),
+    phone       varchar(20) NOT NULL,
+    last_update timestamp NOT NULL DEFAULT now()
+);
+CREATE INDEX idx_fk_city_id ON address (city_id);
+
+CREATE TABLE language (
+    language_id serial PRIMARY KEY,
+    name        character(20) NOT NULL,
+    last_update timestamp NOT NULL DEFAULT now()
+);
+
+CREATE TABLE film (
+    film_id              serial PRIMARY KEY,
+    title                varchar(255) NOT NULL,
+    description          text,
+    release_year         year,
+    language_id          smallint NOT NULL REFERENCES language (language_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    original_language_id smallint REFERENCES language (language_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    rental_duration      smallint NOT NULL DEFAULT 3,
+    rental_rate          numeric(4,2) NOT NULL DEFAULT 4.99,
+    length               smallint,
+    replacement_cost     numeric(5,2) NOT NULL DEFAULT 19.99,
+    rating               mpaa_rating DEFAULT 'G',
+    last_update          timestamp NOT NULL DEFAULT now(),
+    special_features     text[],
+    fulltext             tsvector NOT NULL
+);
+CREATE INDEX film_fulltext_idx ON film USING gist (fulltext);
+CREATE INDEX idx_title ON film (title);
+CREATE INDEX idx_fk_language_id ON film (language_id);
+CREATE INDEX idx_fk_original_language_id ON film (original_language_id);
+
+CREATE TABLE film_actor (
+    actor_id    smallint NOT NULL REFERENCES actor (actor_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    film_id     smallint NOT NULL REFERENCES film (film_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    last_update timestamp NOT NULL DEFAULT now(),
+    PRIMARY KEY (actor_id, film_id)
+);
+CREATE INDEX idx_fk_film_id ON film_actor (film_id);
+
+CREATE TABLE film_category (
+    film_id     smallint NOT NULL REFERENCES film (film_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    category_id smallint NOT NULL REFERENCES category (category_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    last_update timestamp NOT NULL DEFAULT now(),
+    PRIMARY KEY (film_id, category_id)
+);
+
+CREATE TABLE staff (
+    staff_id    serial PRIMARY KEY,
+    first_name  varchar(45) NOT NULL,
+    last_name   varchar(45) NOT NULL,
+    address_id  smallint NOT NULL REFERENCES address (address_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    email       varchar(50),
+    store_id    smallint NOT NULL,
+    active      boolean NOT NULL DEFAULT true,
+    username    varchar(16) NOT NULL,
+    password    varchar(40),
+    last_update timestamp NOT NULL DEFAULT now(),
+    picture     bytea
+);
+
+CREATE TABLE store (
+    store_id         serial PRIMARY KEY,
+    manager_staff_id smallint NOT NULL REFERENCES staff (staff_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    address_id       smallint NOT NULL REFERENCES address (address_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    last_update      timestamp NOT NULL DEFAULT now()
+);
+CREATE UNIQUE INDEX idx_unq_manager_staff_id ON store (manager_staff_id);
+
+ALTER TABLE staff
+    ADD CONSTRAINT staff_store_id_fkey FOREIGN KEY (store_id) REFERENCES store (store_id)
+    DEFERRABLE INITIALLY DEFERRED;
+
+CREATE TABLE customer (
+    customer_id serial PRIMARY KEY,
+    store_id    smallint NOT NULL REFERENCES store (store_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    first_name  varchar(45) NOT NULL,
+    last_name   varchar(45) NOT NULL,
+    email       varchar(50),
+    address_id  smallint NOT NULL REFERENCES address (address_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    activebool  boolean NOT NULL DEFAULT true,
+    create_date date NOT NULL DEFAULT CURRENT_DATE,
+    last_update timestamp DEFAULT now(),
+    active      integer
+);
+CREATE INDEX idx_fk_address_id ON customer (address_id);
+CREATE INDEX idx_fk_store_id ON customer (store_id);
+CREATE INDEX idx_last_name ON customer (last_name);
+
+CREATE TABLE inventory (
+    inventory_id serial PRIMARY KEY,
+    film_id      smallint NOT NULL REFERENCES film (film_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    store_id     smallint NOT NULL REFERENCES store (store_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    last_update  timestamp NOT NULL DEFAULT now()
+);
+CREATE INDEX idx_store_id_film_id ON inventory (store_id, film_id);
+
+CREATE TABLE rental (
+    rental_id    serial PRIMARY KEY,
+    rental_date  timestamp NOT NULL,
+    inventory_id integer NOT NULL REFERENCES inventory (inventory_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    customer_id  smallint NOT NULL REFERENCES customer (customer_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    return_date  timestamp,
+    staff_id     smallint NOT NULL REFERENCES staff (staff_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    last_update  timestamp NOT NULL DEFAULT now()
+);
+CREATE UNIQUE INDEX idx_unq_rental_rental_date_inventory_id_customer_id
+    ON rental (rental_date, inventory_id, customer_id);
+CREATE INDEX idx_fk_inventory_id ON rental (inventory_id);
+
+CREATE TABLE payment (
+    payment_id   serial PRIMARY KEY,
+    customer_id  smallint NOT NULL REFERENCES customer (customer_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    staff_id     smallint NOT NULL REFERENCES staff (staff_id) ON UPDATE CASCADE ON DELETE RESTRICT,
+    rental_id    integer NOT NULL REFERENCES rental (rental_id) ON UPDATE CASCADE ON DELETE SET NULL,
+    amount       numeric(5,2) NOT NULL,
+    payment_date timestamp NOT NULL
+);
+CREATE INDEX idx_fk_customer_id ON payment (customer_id);
+CREATE INDEX idx_fk_staff_id ON payment (staff_id);
+
+CREATE TRIGGER last_updated BEFORE UPDATE ON actor FOR EACH ROW EXECUTE PROCEDURE last_updated();
+CREATE TRIGGER last_updated BEFORE UPDATE ON address FOR EACH ROW EXECUTE PROCEDURE last_updated();
+CREATE TRIGGER last_updated BEFORE UPDATE ON category FOR EACH ROW EXECUTE PROCEDURE last_updated();
+CREATE TRIGGER last_updated BEFORE UPDATE ON city FOR EACH ROW EXECUTE PROCEDURE last_updated();
+CREATE TRIGGER last_updated BEFORE UPDATE ON country FOR EACH ROW EXECUTE PROCEDURE last_updated();
+CREATE TRIGGER last_updated BEFORE UPDATE ON customer FOR EACH ROW EXECUTE PROCEDURE last_updated();
+CREATE TRIGGER last_updated BEFORE UPDATE ON film FOR EACH ROW EXECUTE PROCEDURE last_updated();
+CREATE TRIGGER last_updated BEFORE UPDATE ON film_actor FOR EACH ROW EXECUTE PROCEDURE last_updated();
+CREATE TRIGGER last_updated BEFORE UPDATE ON film_category FOR EACH ROW EXECUTE PROCEDURE last_updated();
+CREATE TRIGGER last_updated BEFORE UPDATE ON inventory FOR EACH ROW EXECUTE PROCEDURE last_updated();
+CREATE TRIGGER last_updated BEFORE UPDATE ON language FOR EACH ROW EXECUTE PROCEDURE last_updated();
+CREATE TRIGGER last_updated BEFORE UPDATE ON rental FOR EACH ROW EXECUTE PROCEDURE last_updated();
+CREATE TRIGGER last_updated BEFORE UPDATE ON staff FOR EACH ROW EXECUTE PROCEDURE last_updated();
+CREATE TRIGGER last_updated BEFORE UPDATE ON store FOR EACH ROW EXECUTE PROCEDURE last_updated();
+
+CREATE TRIGGER film_fulltext_trigger BEFORE INSERT OR UPDATE ON film
+    FOR EACH ROW EXECUTE PROCEDURE tsvector_update_trigger(fulltext, 'pg_catalog.english', title, description);
+
+CREATE FUNCTION inventory_in_stock(p_inventory_id integer) RETURNS boolean
+    LANGUAGE plpgsql
+AS $$
+DECLARE
+    v_rentals integer;
+    v_out     integer;
+BEGIN
+    SELECT count(*) INTO v_rentals
+    FROM rental
+    WHERE inventory_id = p_inventory_id;
+
+    IF v_rentals = 0 THEN
+        RETURN TRUE;
+    END IF;
+
+    SELECT count(rental_id) INTO v_out
+    FROM inventory LEFT JOIN rental USING (inventory_id)
+    WHERE inventory.inventory_id = p_inventory_id
+      AND rental.return_date IS NULL;
+
+    RETURN v_out = 0;
+END $$;
+
+CREATE FUNCTION inventory_held_by_customer(p_inventory_id integer) RETURNS integer
+    LANGUAGE plpgsql
+AS $$
+DECLARE
+    v_customer_id integer;
+BEGIN
+    SELECT customer_id INTO v_customer_id
+    FROM rental
+    WHERE return_date IS NULL
+      AND inventory_id = p_inventory_id;
+
+    RETURN v_customer_id;
+END $$;
+
+CREATE FUNCTION film_in_stock(p_film_id integer, p_store_id integer, OUT p_film_count integer) RETURNS SETOF integer
+    LANGUAGE sql
+AS $$
+    SELECT inventory_id
+    FROM inventory
+    WHERE film_id = $1
+      AND store_id = $2
+      AND inventory_in_stock(inventory_id);
+$$;
+
+-- Rental fees for every rental up to the effective date, one dollar per day
+-- overdue, and the replacement cost once a rental is more than twice its
+-- rental duration overdue, minus everything already paid.
+CREATE FUNCTION get_customer_balance(p_customer_id integer, p_effective_date timestamp) RETURNS numeric
+    LANGUAGE plpgsql
+AS $$
+DECLARE
+    v_rentfees numeric(10,2);
+    v_overfees numeric(10,2);
+    v_payments numeric(10,2);
+BEGIN
+    SELECT coalesce(sum(film.rental_rate), 0) INTO v_rentfees
+    FROM film, inventory, rental
+    WHERE film.film_id = inventory.film_id
+      AND inventory.inventory_id = rental.inventory_id
+      AND rental.rental_date <= p_effective_date
+      AND rental.customer_id = p_customer_id;
+
+    SELECT coalesce(sum(
+        CASE
+            WHEN coalesce(rental.return_date, p_effective_date) - rental.rental_date
+                 > film.rental_duration * 2 * interval '1 day'
+                THEN film.replacement_cost
+            WHEN coalesce(rental.return_date, p_effective_date) - rental.rental_date
+                 > film.rental_duration * interval '1 day'
+                THEN extract(day FROM coalesce(rental.return_date, p_effective_date)
+                                      - rental.rental_date
+                                      - film.rental_duration * interval '1 day')
+            ELSE 0
+        END), 0) INTO v_overfees
+    FROM rental, inventory, film
+    WHERE film.film_id = inventory.film_id
+      AND inventory.inventory_id = rental.inventory_id
+      AND rental.rental_date <= p_effective_date
+      AND rental.customer_id = p_customer_id;
+
+    SELECT coalesce(sum(payment.amount), 0) INTO v_payments
+    FROM payment
+    WHERE payment.payment_date <= p_effective_date
+      AND payment.customer_id = p_customer_id;
+
+    RETURN v_rentfees + v_overfees - v_payments;
+END $$;
+
+CREATE VIEW customer_list AS
+SELECT cu.customer_id AS id,
+       cu.first_name || ' ' || cu.last_name AS name,
+       a.address,
+       a.postal_code AS "zip code",
+       a.phone,
+       city.city,
+       country.country,
+       CASE WHEN cu.activebool THEN 'active' ELSE '' END AS notes,
+       cu.store_id AS sid
+FROM customer cu
+JOIN address a ON cu.address_id = a.address_id
+JOIN city ON a.city_id = city.city_id
+JOIN country ON city.country_id = country.country_id;
+
+CREATE VIEW film_list AS
+SELECT film.film_id AS fid,
+       film.title,
+       film.description,
+       category.name AS category,
+       film.rental_rate AS price,
+       film.length,
+       film.rating,
+       string_agg(actor.first_name || ' ' || actor.last_name, ', ') AS actors
+FROM category
+LEFT JOIN film_category ON category.category_id = film_category.category_id
+LEFT JOIN film ON film_category.film_id = film.film_id
+JOIN film_actor ON film.film_id = film_actor.film_id
+JOIN actor ON film_actor.actor_id = actor.actor_id
+GROUP BY film.film_id, film.title, film.description, category.name, film.rental_rate, film.length, film.rating;
@@ -0,0 +1,14 @@
+-- Used by GET /api/actors/actor-film-in-category/{actor_id}/{category_id}:
+-- the actor's name and the titles of their films in the category.
+CREATE OR REPLACE FUNCTION get_actor_film_in_category(p_actor_id integer, p_category_id integer)
+    RETURNS TABLE (first_name varchar, last_name varchar, titles json)
+    LANGUAGE sql
+AS $$
+    SELECT a.first_name, a.last_name, coalesce(json_agg(f.title ORDER BY f.title) FILTER (WHERE f.film_id IS NOT NULL), '[]')
+    FROM actor a
+    LEFT JOIN film_actor fa ON fa.actor_id = a.actor_id
+    LEFT JOIN film_category fc ON fc.film_id = fa.film_id AND fc.category_id = p_category_id
+    LEFT JOIN film f ON f.film_id = fc.film_id
+    WHERE a.actor_id = p_actor_id
+    GROUP BY a.actor_id, a.first_name, a.last_name;
+$$;
@@ -0,0 +1,4 @@
+-- Retired copies keep their row so rental and payment history stays intact.
+ALTER TABLE inventory ADD COLUMN retired_at timestamp;
+
+CREATE INDEX idx_inventory_active_store_id ON inventory (store_id) WHERE retired_at IS NULL;
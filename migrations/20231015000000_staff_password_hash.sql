@@ -0,0 +1,4 @@
+-- Argon2 PHC strings don't fit the original varchar(40) sha1 column.
+ALTER TABLE staff ALTER COLUMN password TYPE text;
+
+CREATE UNIQUE INDEX idx_unq_staff_username ON staff (username);
@@ -0,0 +1,13 @@
+-- Keys for machine clients. Only a sha256 digest of the key is stored; the
+-- plaintext is shown once when the key is created.
+CREATE TABLE api_key (
+    api_key_id serial PRIMARY KEY,
+    name text NOT NULL,
+    prefix text NOT NULL,
+    key_hash text NOT NULL UNIQUE,
+    scopes text[] NOT NULL CHECK (scopes <@ ARRAY['reports:read', 'catalogue:write']),
+    created_by integer REFERENCES staff (staff_id) ON UPDATE CASCADE ON DELETE SET NULL,
+    created_at timestamp NOT NULL DEFAULT now(),
+    last_used_at timestamp,
+    revoked_at timestamp
+);
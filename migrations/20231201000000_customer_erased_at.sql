@@ -0,0 +1,3 @@
+-- Erased customers keep their row, rentals and payments for accounting; only
+-- their personal data is overwritten.
+ALTER TABLE customer ADD COLUMN erased_at timestamp;
@@ -0,0 +1,8 @@
+-- Trigram indexes behind /api/customers/search: they serve both the `ILIKE
+-- '%term%'` substring matches and the fuzzy `<%` name matches.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX idx_customer_full_name_trgm ON customer USING gin ((first_name || ' ' || last_name) gin_trgm_ops);
+CREATE INDEX idx_customer_email_trgm ON customer USING gin (email gin_trgm_ops);
+CREATE INDEX idx_address_phone_trgm ON address USING gin (phone gin_trgm_ops);
+CREATE INDEX idx_address_postal_code_trgm ON address USING gin (postal_code gin_trgm_ops);
@@ -0,0 +1,6 @@
+-- Named counters behind /counter, shared by every worker and instance.
+CREATE TABLE counter (
+    name varchar(50) PRIMARY KEY,
+    value integer NOT NULL DEFAULT 0,
+    last_update timestamp NOT NULL DEFAULT now()
+);
@@ -0,0 +1,2 @@
+-- The staff member who checked a rental back in; `staff_id` is who rented it out.
+ALTER TABLE rental ADD COLUMN returned_by smallint REFERENCES staff (staff_id);
@@ -0,0 +1,125 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+use crate::metrics::Timed;
+use crate::models::ApiError;
+use crate::AppState;
+
+pub const API_KEY_HEADER: &str = "X-Api-Key";
+
+const KEY_PREFIX: &str = "frk_";
+
+/// What a machine client may do. Every valid key can read; write scopes open up
+/// the write routes of the matching `api_routes` scopes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum ApiScope {
+    #[serde(rename = "reports:read")]
+    ReportsRead,
+    #[serde(rename = "catalogue:write")]
+    CatalogueWrite,
+}
+
+impl ApiScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ReportsRead => "reports:read",
+            Self::CatalogueWrite => "catalogue:write",
+        }
+    }
+
+    fn parse(scope: &str) -> Option<Self> {
+        match scope {
+            "reports:read" => Some(Self::ReportsRead),
+            "catalogue:write" => Some(Self::CatalogueWrite),
+            _ => None,
+        }
+    }
+}
+
+/// The API key client behind the request's `X-Api-Key` header.
+#[derive(Clone, Serialize)]
+pub struct ApiClient {
+    pub api_key_id: i32,
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+}
+
+impl ApiClient {
+    pub fn has_scope(&self, scope: ApiScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+#[derive(FromRow)]
+struct ApiKeyRow {
+    api_key_id: i32,
+    name: String,
+    scopes: Vec<String>,
+}
+
+/// A freshly generated key. `key` is only ever returned to the caller that created it.
+pub struct GeneratedKey {
+    pub key: String,
+    pub prefix: String,
+    pub hash: String,
+}
+
+pub fn generate() -> GeneratedKey {
+    let mut bytes = [0u8; 24];
+    OsRng.fill_bytes(&mut bytes);
+    let secret: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    let key = format!("{KEY_PREFIX}{secret}");
+    GeneratedKey {
+        prefix: key[..KEY_PREFIX.len() + 8].to_string(),
+        hash: hash(&key),
+        key,
+    }
+}
+
+/// Keys are long random strings, so an unsalted digest is enough to look them up by.
+pub fn hash(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// Resolves the `X-Api-Key` header into an `ApiClient` request extension and
+/// records when the key was last used. Unknown or revoked keys are rejected;
+/// requests without the header pass through.
+pub async fn authenticate_api_key(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    if let Some(header) = req.headers().get(API_KEY_HEADER) {
+        let key = header
+            .to_str()
+            .map_err(|_| ApiError::unauthorized("Invalid API key"))?;
+        let state = req
+            .app_data::<web::Data<AppState>>()
+            .expect("AppState is registered on the App");
+        let row = sqlx::query_as::<_, ApiKeyRow>("
+        UPDATE api_key
+        SET last_used_at = now()
+        WHERE key_hash = $1
+        AND revoked_at IS NULL
+        RETURNING api_key_id, name, scopes
+        ")
+            .bind(hash(key))
+            .fetch_optional(&state.db)
+            .timed("authenticate_api_key")
+            .await
+            .map_err(ApiError::from)?
+            .ok_or_else(|| ApiError::unauthorized("Invalid API key"))?;
+        req.extensions_mut().insert(ApiClient {
+            api_key_id: row.api_key_id,
+            name: row.name,
+            scopes: row.scopes.iter().filter_map(|scope| ApiScope::parse(scope)).collect(),
+        });
+    }
+    next.call(req).await
+}
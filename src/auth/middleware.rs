@@ -0,0 +1,109 @@
+use std::future::{ready, Ready};
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest};
+use serde::Serialize;
+use sqlx::FromRow;
+
+use crate::metrics::Timed;
+use crate::models::ApiError;
+use crate::AppState;
+
+use super::{token, Role};
+
+/// The active staff member behind the request's bearer token.
+#[derive(Clone, Serialize)]
+pub struct AuthenticatedStaff {
+    pub staff_id: i32,
+    pub store_id: i16,
+    pub username: String,
+    pub role: Role,
+}
+
+#[derive(FromRow)]
+struct StaffRow {
+    staff_id: i32,
+    store_id: i16,
+    username: String,
+    is_manager: bool,
+}
+
+impl AuthenticatedStaff {
+    pub fn require(&self, role: Role) -> Result<(), ApiError> {
+        if self.role < role {
+            return Err(ApiError::forbidden("Your role does not allow this action"));
+        }
+        Ok(())
+    }
+
+    /// `rental.staff_id` and `payment.staff_id` are `smallint`, unlike `staff.staff_id`.
+    pub fn staff_id_i16(&self) -> Result<i16, ApiError> {
+        i16::try_from(self.staff_id)
+            .map_err(|_| ApiError::Internal(format!("Staff id {} does not fit a smallint column", self.staff_id)))
+    }
+
+    /// Staff can only change customers and inventory that belong to their own store.
+    pub fn require_store(&self, store_id: i16) -> Result<(), ApiError> {
+        if self.store_id != store_id {
+            return Err(ApiError::forbidden(format!("Staff of store {} cannot manage store {store_id}", self.store_id)));
+        }
+        Ok(())
+    }
+}
+
+/// Resolves `Authorization: Bearer <token>` into an `AuthenticatedStaff` request
+/// extension. Requests without the header pass through anonymously; handlers
+/// that need a staff member take `AuthenticatedStaff` as an extractor.
+pub async fn authenticate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    if let Some(header) = req.headers().get(AUTHORIZATION) {
+        let bearer = header
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| ApiError::unauthorized("Expected a bearer token"))?;
+        let state = req
+            .app_data::<web::Data<AppState>>()
+            .expect("AppState is registered on the App");
+        let claims = token::verify(bearer, &state.jwt_secret)?;
+        let row = sqlx::query_as::<_, StaffRow>("
+        SELECT staff_id, store_id, username,
+            EXISTS(SELECT 1 FROM store WHERE store.manager_staff_id = staff.staff_id) as is_manager
+        FROM staff
+        WHERE staff_id = $1
+        AND active
+        ")
+            .bind(claims.sub)
+            .fetch_optional(&state.db)
+            .timed("authenticate")
+            .await
+            .map_err(ApiError::from)?
+            .ok_or_else(|| ApiError::unauthorized("Staff member is no longer active"))?;
+        req.extensions_mut().insert(AuthenticatedStaff {
+            staff_id: row.staff_id,
+            store_id: row.store_id,
+            username: row.username,
+            role: if row.is_manager { Role::Manager } else { Role::Clerk },
+        });
+    }
+    next.call(req).await
+}
+
+impl FromRequest for AuthenticatedStaff {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<AuthenticatedStaff>()
+                .cloned()
+                .ok_or_else(|| ApiError::unauthorized("Authentication required")),
+        )
+    }
+}
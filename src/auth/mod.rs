@@ -0,0 +1,9 @@
+pub mod api_key;
+mod middleware;
+pub mod password;
+mod role;
+pub mod token;
+
+pub use api_key::{authenticate_api_key, ApiClient, ApiScope};
+pub use middleware::{authenticate, AuthenticatedStaff};
+pub use role::{require_write_access, ReportReader, Role, WriteAccess};
\ No newline at end of file
@@ -0,0 +1,49 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use sha1::{Digest, Sha1};
+use subtle::ConstantTimeEq;
+
+use crate::models::ApiError;
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Hashes a new password, enforcing the minimum length.
+pub fn hash(password: &str) -> Result<String, ApiError> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(ApiError::bad_request(format!(
+            "password must be at least {MIN_PASSWORD_LENGTH} characters"
+        )));
+    }
+    rehash(password)
+}
+
+/// Hashes an already accepted password, e.g. when upgrading a legacy hash on login.
+pub fn rehash(password: &str) -> Result<String, ApiError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to hash password");
+            ApiError::Internal("Failed to hash password".to_string())
+        })
+}
+
+/// Checks `password` against a stored argon2 hash, or against the unsalted
+/// sha1 hex digests the Pagila sample data ships with. Anything else never matches.
+pub fn verify(password: &str, stored: &str) -> bool {
+    if is_legacy(stored) {
+        let digest = format!("{:x}", Sha1::digest(password.as_bytes()));
+        return digest.as_bytes().ct_eq(stored.to_ascii_lowercase().as_bytes()).into();
+    }
+    match PasswordHash::new(stored) {
+        Ok(hash) => Argon2::default().verify_password(password.as_bytes(), &hash).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// A sha1 hex digest, which should be replaced with argon2 on the next successful login.
+pub fn is_legacy(stored: &str) -> bool {
+    stored.len() == 40 && stored.bytes().all(|b| b.is_ascii_hexdigit())
+}
@@ -0,0 +1,79 @@
+use std::future::{ready, Ready};
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest};
+use serde::Serialize;
+
+use crate::models::ApiError;
+
+use super::{ApiClient, ApiScope, AuthenticatedStaff};
+
+/// What a staff member may do, lowest first. There is no role column: a staff
+/// member is the `Manager` of the store whose `manager_staff_id` points at them
+/// and a `Clerk` otherwise. Read-only clients use API keys instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Clerk,
+    Manager,
+}
+
+/// Who may write to a scope: staff with at least `role`, or API clients holding `api_scope`.
+#[derive(Clone, Copy)]
+pub struct WriteAccess {
+    pub role: Role,
+    pub api_scope: Option<ApiScope>,
+}
+
+/// Scope middleware that lets reads through and rejects writes from callers
+/// without the scope's `WriteAccess`. Use it as
+/// `.wrap(from_fn(|req, next| require_write_access(req, next, ACCESS)))`.
+pub async fn require_write_access(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+    access: WriteAccess,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let method = req.method();
+    if method != Method::GET && method != Method::HEAD && method != Method::OPTIONS {
+        let staff_role = req.extensions().get::<AuthenticatedStaff>().map(|staff| staff.role);
+        let client = req.extensions().get::<ApiClient>().cloned();
+        let denied = match (staff_role, client) {
+            (Some(role), _) if role >= access.role => None,
+            (Some(_), _) => Some(ApiError::forbidden("Your role does not allow this action")),
+            (None, Some(client)) if access.api_scope.is_some_and(|scope| client.has_scope(scope)) => None,
+            (None, Some(_)) => Some(ApiError::forbidden("API key is missing the scope for this action")),
+            (None, None) => Some(ApiError::unauthorized("Authentication required")),
+        };
+        if let Some(err) = denied {
+            return Err(err.into());
+        }
+    }
+    next.call(req).await
+}
+
+/// Who is reading a report: any staff member, or an API client holding the
+/// `reports:read` scope. Reporting handlers take it as an extractor, which
+/// rejects anonymous callers and keys without the scope.
+pub enum ReportReader {
+    Staff(AuthenticatedStaff),
+    Client(ApiClient),
+}
+
+impl FromRequest for ReportReader {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let extensions = req.extensions();
+        let reader = match (extensions.get::<AuthenticatedStaff>(), extensions.get::<ApiClient>()) {
+            (Some(staff), _) => Ok(Self::Staff(staff.clone())),
+            (None, Some(client)) if client.has_scope(ApiScope::ReportsRead) => Ok(Self::Client(client.clone())),
+            (None, Some(_)) => Err(ApiError::forbidden("API key is missing the scope for this action")),
+            (None, None) => Err(ApiError::unauthorized("Authentication required")),
+        };
+        ready(reader)
+    }
+}
@@ -0,0 +1,35 @@
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::models::ApiError;
+
+/// Tokens cover one shift.
+pub const TOKEN_TTL_SECONDS: i64 = 8 * 60 * 60;
+
+#[derive(Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: i32,
+    pub store_id: i16,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+pub fn issue(staff_id: i32, store_id: i16, secret: &str) -> Result<String, ApiError> {
+    let now = chrono::Utc::now().timestamp();
+    let claims = Claims {
+        sub: staff_id,
+        store_id,
+        iat: now,
+        exp: now + TOKEN_TTL_SECONDS,
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes())).map_err(|e| {
+        tracing::error!(error = %e, "Failed to issue token");
+        ApiError::Internal("Failed to issue token".to_string())
+    })
+}
+
+pub fn verify(token: &str, secret: &str) -> Result<Claims, ApiError> {
+    decode::<Claims>(token, &DecodingKey::from_secret(secret.as_bytes()), &Validation::default())
+        .map(|data| data.claims)
+        .map_err(|_| ApiError::unauthorized("Invalid or expired token"))
+}
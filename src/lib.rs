@@ -0,0 +1,69 @@
+pub mod auth;
+pub mod metrics;
+pub mod models;
+pub mod routes;
+pub mod schema;
+pub mod settings;
+pub mod telemetry;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::{middleware, web, App, Error};
+use sqlx::{Pool, Postgres};
+use actix_cors::Cors;
+use models::ApiError;
+
+pub struct AppState {
+    pub db: Pool<Postgres>,
+    pub jwt_secret: String,
+}
+
+/// Outside production an empty origin list keeps the old permissive behaviour for
+/// local development; in production only the configured origins are allowed.
+pub fn cors(allowed_origins: &[String], production: bool) -> Cors {
+    if allowed_origins.is_empty() && !production {
+        return Cors::permissive();
+    }
+    allowed_origins
+        .iter()
+        .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+        .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE"])
+        .allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT, header::CONTENT_TYPE])
+        .allowed_header(auth::api_key::API_KEY_HEADER)
+        .max_age(3600)
+}
+
+/// The application served by `main.rs`, shared with the integration tests.
+pub fn app(
+    app_state: web::Data<AppState>,
+    cors: Cors,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = Error,
+        InitError = (),
+    >,
+> {
+    let counter = web::scope("/counter").configure(routes::counter_routes);
+    let api = web::scope("/api")
+        .wrap(middleware::from_fn(auth::authenticate))
+        .configure(routes::api_routes);
+    App::new()
+        .wrap(middleware::from_fn(auth::authenticate_api_key))
+        .wrap(cors)
+        .wrap(middleware::from_fn(metrics::record_request))
+        .wrap(middleware::from_fn(telemetry::trace_request))
+        .app_data(app_state)
+        .app_data(web::JsonConfig::default()
+            .error_handler(|err, _req| ApiError::bad_request(err.to_string()).into()))
+        .app_data(web::QueryConfig::default()
+            .error_handler(|err, _req| ApiError::bad_request(err.to_string()).into()))
+        .app_data(web::PathConfig::default()
+            .error_handler(|err, _req| ApiError::not_found(err.to_string()).into()))
+        .configure(routes::probe_routes)
+        .service(counter)
+        .service(api)
+}
@@ -1,56 +1,46 @@
-mod routes;
-mod models;
-
-use actix_web::{web, App, HttpServer};
+use actix_web::{web, HttpServer};
 use dotenv::dotenv;
-use sqlx;
 use sqlx::postgres::PgPoolOptions;
-use sqlx::{Pool, Postgres};
-use std::sync::Mutex;
-use actix_cors::Cors;
 
-pub struct AppState {
-    pub counter: Mutex<i32>,
-    pub db: Pool<Postgres>,
-}
+use film_rental_rust::settings::Settings;
+use film_rental_rust::{app, cors, models, schema, telemetry, AppState};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
-    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    telemetry::init();
+    let settings = Settings::load().unwrap_or_else(|e| panic!("Invalid settings: {e}"));
+    models::hide_internal_details(settings.production);
 
     let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
+        .min_connections(settings.database.min_connections)
+        .max_connections(settings.database.max_connections)
+        .acquire_timeout(settings.database.acquire_timeout())
+        .idle_timeout(settings.database.idle_timeout())
+        .connect(&settings.database.url)
         .await
         .expect("Error connecting to DB");
 
+    if std::env::args().any(|arg| arg == "--migrate") {
+        schema::migrate(&pool).await.expect("Error running migrations");
+        tracing::info!("Database schema is up to date");
+        return Ok(());
+    }
+    schema::check(&pool).await.unwrap_or_else(|e| panic!("{e}"));
+
     let app_state = web::Data::new(AppState {
-        counter: Mutex::new(0),
         db: pool.clone(),
+        jwt_secret: settings.jwt_secret.clone(),
     });
 
-    HttpServer::new(move || {
-        let counter = web::scope("/counter").configure(routes::counter_routes);
-        let api = web::scope("/api").configure(routes::api_routes);
-        let cors = Cors::permissive();
-
-        // let cors = Cors::default()
-        //     .allowed_origin("http://localhost:4200")
-        //     .allowed_origin_fn(|origin, _req_head| {
-        //         origin.as_bytes().ends_with(b".localhost:4200")
-        //     })
-        //     .allowed_methods(vec!["GET", "POST"])
-        //     .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
-        //     .allowed_header(http::header::CONTENT_TYPE)
-        //     .max_age(3600);
-        App::new()
-            .wrap(cors)
-            .app_data(app_state.clone())
-            .service(counter)
-            .service(api)
-    })
-    .bind(("127.0.0.1", 8080))?
-    .run()
-    .await
-}
+    let allowed_origins = settings.cors.allowed_origins.clone();
+    let production = settings.production;
+    let mut server = HttpServer::new(move || app(app_state.clone(), cors(&allowed_origins, production)));
+    if let Some(workers) = settings.server.workers {
+        server = server.workers(workers);
+    }
+    server
+        .bind((settings.server.host.as_str(), settings.server.port))?
+        .run()
+        .await
+}
\ No newline at end of file
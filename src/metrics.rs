@@ -0,0 +1,189 @@
+use std::future::Future;
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::Error;
+use prometheus::{
+    HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::{Pool, Postgres};
+use tracing::{Instrument, Span};
+
+use crate::telemetry;
+
+/// Prometheus metrics served at `/metrics`. Routes are labelled with their
+/// pattern (`/api/actors/{id}`) so the number of series stays bounded.
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    query_duration: HistogramVec,
+    query_errors: IntCounterVec,
+    pool_connections: IntGaugeVec,
+    pool_max_connections: IntGauge,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+impl Metrics {
+    fn new() -> Self {
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route pattern and status code"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route pattern"),
+            &["method", "route"],
+        )
+        .unwrap();
+        let query_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "db_query_duration_seconds",
+                "SQL query latency, including the wait for a pool connection",
+            ),
+            &["query"],
+        )
+        .unwrap();
+        let query_errors = IntCounterVec::new(
+            Opts::new("db_query_errors_total", "SQL queries that failed, not counting empty results"),
+            &["query"],
+        )
+        .unwrap();
+        let pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Open pool connections by state (idle or in_use)"),
+            &["state"],
+        )
+        .unwrap();
+        let pool_max_connections = IntGauge::new("db_pool_max_connections", "Configured pool size limit").unwrap();
+
+        let registry = Registry::new();
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry.register(Box::new(http_duration.clone())).unwrap();
+        registry.register(Box::new(query_duration.clone())).unwrap();
+        registry.register(Box::new(query_errors.clone())).unwrap();
+        registry.register(Box::new(pool_connections.clone())).unwrap();
+        registry.register(Box::new(pool_max_connections.clone())).unwrap();
+
+        Self {
+            registry,
+            http_requests,
+            http_duration,
+            query_duration,
+            query_errors,
+            pool_connections,
+            pool_max_connections,
+        }
+    }
+}
+
+/// App middleware counting and timing every request. Requests that match no
+/// route share the `unmatched` label.
+pub async fn record_request(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let method = req.method().to_string();
+    let route = req.match_pattern().unwrap_or_else(|| "unmatched".to_string());
+    let started = Instant::now();
+    let res = next.call(req).await;
+    let status = match &res {
+        Ok(res) => res.status(),
+        Err(err) => err.as_response_error().status_code(),
+    };
+    METRICS
+        .http_duration
+        .with_label_values(&[&method, &route])
+        .observe(started.elapsed().as_secs_f64());
+    METRICS
+        .http_requests
+        .with_label_values(&[&method, &route, status.as_str()])
+        .inc();
+    res
+}
+
+/// Times a sqlx future under a query name and runs it in a `db.query` span, e.g.
+/// `.fetch_one(&state.db).timed("get_actor").await?`. Failures are logged with
+/// the statement sqlx ran.
+pub trait Timed<T>: Future<Output = Result<T, sqlx::Error>> + Sized {
+    fn timed(self, query: &'static str) -> impl Future<Output = Result<T, sqlx::Error>> {
+        let span = tracing::info_span!("db.query", query);
+        async move {
+            let started = Instant::now();
+            let result = self.instrument(span.clone()).await;
+            let elapsed = started.elapsed();
+            METRICS
+                .query_duration
+                .with_label_values(&[query])
+                .observe(elapsed.as_secs_f64());
+            match &result {
+                Ok(_) | Err(sqlx::Error::RowNotFound) => span.in_scope(|| {
+                    tracing::debug!(elapsed_ms = elapsed.as_secs_f64() * 1000.0, "Query finished");
+                }),
+                Err(e) => {
+                    METRICS.query_errors.with_label_values(&[query]).inc();
+                    log_query_error(&span, e);
+                }
+            }
+            result
+        }
+    }
+}
+
+impl<T, F: Future<Output = Result<T, sqlx::Error>>> Timed<T> for F {}
+
+/// Database errors (constraint violations and the like) usually become 4xx
+/// responses, so only connection and driver failures are logged as errors.
+fn log_query_error(span: &Span, e: &sqlx::Error) {
+    let statement = telemetry::statement(span);
+    let _entered = span.enter();
+    match e.as_database_error() {
+        Some(db) => tracing::warn!(
+            error.kind = "database",
+            error.code = db.code().as_deref(),
+            error.constraint = db.constraint(),
+            db.statement = statement.as_deref(),
+            "Query failed: {e}"
+        ),
+        None => tracing::error!(
+            error.kind = error_kind(e),
+            db.statement = statement.as_deref(),
+            "Query failed: {e}"
+        ),
+    }
+}
+
+fn error_kind(e: &sqlx::Error) -> &'static str {
+    match e {
+        sqlx::Error::Io(_) | sqlx::Error::Tls(_) => "io",
+        sqlx::Error::Protocol(_) => "protocol",
+        sqlx::Error::PoolTimedOut => "pool_timed_out",
+        sqlx::Error::PoolClosed => "pool_closed",
+        sqlx::Error::ColumnNotFound(_)
+        | sqlx::Error::ColumnIndexOutOfBounds { .. }
+        | sqlx::Error::ColumnDecode { .. }
+        | sqlx::Error::Decode(_)
+        | sqlx::Error::TypeNotFound { .. } => "decode",
+        _ => "other",
+    }
+}
+
+/// Renders every metric in the Prometheus text format, with the pool gauges
+/// read from `pool` at scrape time.
+pub fn render(pool: &Pool<Postgres>) -> String {
+    let idle = pool.num_idle() as i64;
+    METRICS.pool_connections.with_label_values(&["idle"]).set(idle);
+    METRICS
+        .pool_connections
+        .with_label_values(&["in_use"])
+        .set(pool.size() as i64 - idle);
+    METRICS
+        .pool_max_connections
+        .set(pool.options().get_max_connections() as i64);
+    TextEncoder::new()
+        .encode_to_string(&METRICS.registry.gather())
+        .expect("Metrics are valid UTF-8")
+}
@@ -0,0 +1,182 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::GenericResponse;
+
+static HIDE_INTERNAL_DETAILS: AtomicBool = AtomicBool::new(false);
+
+/// Set once at startup in production: 500 responses then only say "Internal
+/// server error" and database errors no longer name the violated constraint.
+pub fn hide_internal_details(hide: bool) {
+    HIDE_INTERNAL_DETAILS.store(hide, Ordering::Relaxed);
+}
+
+fn internal_details_hidden() -> bool {
+    HIDE_INTERNAL_DETAILS.load(Ordering::Relaxed)
+}
+
+/// Error returned by handlers. Every variant is rendered through
+/// `GenericResponse::error` with the matching status code.
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    Unprocessable(String),
+    Internal(String),
+    /// A dependency such as the database cannot serve requests right now.
+    Unavailable(String),
+    /// A step of a multi-step write failed. Carries the underlying error and
+    /// tells the client which step (and input field, if any) it was.
+    Step(Box<ApiError>, StepDetails),
+}
+
+/// The `data` of an error response: the failed step of a multi-step write.
+/// Other errors have `null` there.
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+pub struct StepDetails {
+    pub step: &'static str,
+    pub field: Option<&'static str>,
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::BadRequest(message.into())
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::Unauthorized(message.into())
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::Forbidden(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict(message.into())
+    }
+
+    pub fn unprocessable(message: impl Into<String>) -> Self {
+        Self::Unprocessable(message.into())
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::Unavailable(message.into())
+    }
+
+    /// Tags the error with the step of a multi-step write that produced it.
+    pub fn in_step(self, step: &'static str, field: Option<&'static str>) -> Self {
+        match self {
+            Self::Step(inner, _) => Self::Step(inner, StepDetails { step, field }),
+            inner => Self::Step(Box::new(inner), StepDetails { step, field }),
+        }
+    }
+
+    /// `map_err` adapter tagging a database error with the step it happened in.
+    pub fn step(step: &'static str, field: Option<&'static str>) -> impl FnOnce(sqlx::Error) -> Self {
+        move |e| Self::from(e).in_step(step, field)
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::BadRequest(message)
+            | Self::Unauthorized(message)
+            | Self::Forbidden(message)
+            | Self::NotFound(message)
+            | Self::Conflict(message)
+            | Self::Unprocessable(message)
+            | Self::Internal(message)
+            | Self::Unavailable(message) => message,
+            Self::Step(inner, _) => inner.message(),
+        }
+    }
+
+    /// The message sent to the client, which leaves out internal details in production.
+    fn client_message(&self) -> &str {
+        match self {
+            Self::Internal(_) if internal_details_hidden() => "Internal server error",
+            Self::Unavailable(_) if internal_details_hidden() => "Service unavailable",
+            Self::Step(inner, _) => inner.client_message(),
+            _ => self.message(),
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Step(inner, _) => inner.status_code(),
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if self.status_code().is_server_error() {
+            tracing::error!(error = self.message(), "Request failed with an internal error");
+        }
+        let data = match self {
+            Self::Step(_, details) => Some(*details),
+            _ => None,
+        };
+        HttpResponse::build(self.status_code()).json(GenericResponse::error(data, self.client_message().to_string()))
+    }
+}
+
+/// Appends the violated constraint to `message` unless internal details are hidden.
+fn with_constraint(message: &str, constraint: Option<&str>) -> String {
+    match constraint {
+        Some(constraint) if !internal_details_hidden() => format!("{message} ({constraint})"),
+        _ => message.to_string(),
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        match &e {
+            sqlx::Error::RowNotFound => Self::not_found("Resource not found"),
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                Self::conflict(with_constraint("Resource already exists", db.constraint()))
+            }
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => Self::unprocessable(with_constraint(
+                "Referenced resource is missing or still in use",
+                db.constraint(),
+            )),
+            sqlx::Error::Database(db) if db.is_check_violation() => {
+                Self::unprocessable(with_constraint("Value out of range", db.constraint()))
+            }
+            // string_data_right_truncation: input longer than the varchar column
+            sqlx::Error::Database(db) if db.code().as_deref() == Some("22001") => {
+                Self::unprocessable("Value too long")
+            }
+            // numeric_value_out_of_range: arithmetic overflowed the column type
+            sqlx::Error::Database(db) if db.code().as_deref() == Some("22003") => {
+                Self::unprocessable("Value out of range")
+            }
+            _ => Self::Internal(format!("Internal server error: {e}")),
+        }
+    }
+}
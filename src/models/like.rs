@@ -0,0 +1,12 @@
+/// Escapes `\`, `%` and `_` so user input matches literally inside a `LIKE`
+/// pattern. The query must say `ESCAPE '\'` alongside it.
+pub fn escape_like(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
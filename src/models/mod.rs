@@ -1,3 +1,9 @@
+mod error;
+mod like;
+mod pagination;
 mod response;
 
+pub use error::{hide_internal_details, ApiError, StepDetails};
+pub use like::escape_like;
+pub use pagination::{PageParams, PageQuery, Pagination};
 pub use response::GenericResponse;
\ No newline at end of file
@@ -0,0 +1,149 @@
+use std::future::{ready, Ready};
+
+use actix_web::{dev, web, FromRequest, HttpRequest};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use utoipa::{IntoParams, ToSchema};
+
+use super::ApiError;
+
+pub const DEFAULT_PER_PAGE: i64 = 25;
+pub const MAX_PER_PAGE: i64 = 100;
+
+#[derive(Deserialize, Clone, Copy, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// The raw query behind `PageParams`, also listed as the parameters of every
+/// paginated operation in the OpenAPI document.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageQuery {
+    /// 1-based page number, defaults to 1.
+    page: Option<i64>,
+    /// Defaults to 25, capped at 100.
+    per_page: Option<i64>,
+    /// Column to sort by; each endpoint accepts its own set.
+    sort: Option<String>,
+    order: Option<SortOrder>,
+}
+
+/// `?page=&per_page=&sort=&order=` shared by every list endpoint.
+/// `per_page` is capped at `MAX_PER_PAGE`.
+pub struct PageParams {
+    pub page: i64,
+    pub per_page: i64,
+    pub sort: Option<String>,
+    pub order: SortOrder,
+    path: String,
+    query_string: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Pagination {
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+    pub total_pages: i64,
+    pub next: Option<String>,
+    pub prev: Option<String>,
+}
+
+impl PageParams {
+    pub fn offset(&self) -> i64 {
+        (self.page - 1) * self.per_page
+    }
+
+    /// Appends `ORDER BY` for the requested column, which must be one of `allowed`.
+    /// Falls back to `default` when no `sort` was given.
+    pub fn push_order_by(
+        &self,
+        qb: &mut QueryBuilder<'_, Postgres>,
+        allowed: &[&str],
+        default: &str,
+    ) -> Result<(), ApiError> {
+        let column = match self.sort.as_deref() {
+            Some(sort) => *allowed.iter().find(|column| **column == sort).ok_or_else(|| {
+                ApiError::bad_request(format!("Cannot sort by {sort}, expected one of: {}", allowed.join(", ")))
+            })?,
+            None => default,
+        };
+        let order = match self.order {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+        // `column` comes from the whitelist above, so it's safe to inline.
+        qb.push(format_args!(" ORDER BY {column} {order}"));
+        if column != default {
+            qb.push(format_args!(", {default} {order}"));
+        }
+        Ok(())
+    }
+
+    pub fn push_limit(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        qb.push(" LIMIT ").push_bind(self.per_page);
+        qb.push(" OFFSET ").push_bind(self.offset());
+    }
+
+    pub fn pagination(&self, total: i64) -> Pagination {
+        let total_pages = (total + self.per_page - 1) / self.per_page;
+        Pagination {
+            page: self.page,
+            per_page: self.per_page,
+            total,
+            total_pages,
+            next: (self.page < total_pages).then(|| self.link(self.page + 1)),
+            prev: (self.page > 1).then(|| self.link((self.page - 1).min(total_pages.max(1)))),
+        }
+    }
+
+    fn link(&self, page: i64) -> String {
+        let mut params: Vec<&str> = self
+            .query_string
+            .split('&')
+            .filter(|param| !param.is_empty() && !param.starts_with("page=") && !param.starts_with("per_page="))
+            .collect();
+        let page = format!("page={page}");
+        let per_page = format!("per_page={}", self.per_page);
+        params.push(&page);
+        params.push(&per_page);
+        format!("{}?{}", self.path, params.join("&"))
+    }
+}
+
+impl FromRequest for PageParams {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+        let query = match web::Query::<PageQuery>::from_query(req.query_string()) {
+            Ok(query) => query.into_inner(),
+            Err(e) => return ready(Err(ApiError::bad_request(e.to_string()))),
+        };
+        let page = query.page.unwrap_or(1);
+        if page < 1 {
+            return ready(Err(ApiError::bad_request("page must be at least 1")));
+        }
+        let per_page = query.per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if per_page < 1 {
+            return ready(Err(ApiError::bad_request("per_page must be at least 1")));
+        }
+        let per_page = per_page.min(MAX_PER_PAGE);
+        // Keeps `offset()` from overflowing for absurd page numbers.
+        if (page - 1).checked_mul(per_page).is_none() {
+            return ready(Err(ApiError::bad_request("page is too large")));
+        }
+        ready(Ok(Self {
+            page,
+            per_page,
+            sort: query.sort,
+            order: query.order.unwrap_or_default(),
+            path: req.path().to_string(),
+            query_string: req.query_string().to_string(),
+        }))
+    }
+}
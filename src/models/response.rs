@@ -1,10 +1,16 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize,Deserialize)]
+use super::Pagination;
+
+/// Envelope around every JSON body. `status` is `Success` or `Error`.
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct GenericResponse<T, U>{
     status: String,
     data: T,
-    message: U
+    message: U,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pagination: Option<Pagination>,
 }
 
 impl<T, U> GenericResponse<T, U> {
@@ -13,6 +19,15 @@ impl<T, U> GenericResponse<T, U> {
             status: "Success".to_string(),
             message,
             data,
+            pagination: None,
+        }
+    }
+    pub fn paginated(data: T, message: U, pagination: Pagination) -> Self {
+        Self {
+            status: "Success".to_string(),
+            message,
+            data,
+            pagination: Some(pagination),
         }
     }
     pub fn error(data: T, message: U) -> Self {
@@ -20,6 +35,7 @@ impl<T, U> GenericResponse<T, U> {
             status: "Error".to_string(),
             message,
             data,
+            pagination: None,
         }
     }
 }
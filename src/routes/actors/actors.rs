@@ -1,12 +1,14 @@
 use crate::AppState;
-use crate::models::GenericResponse;
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
+use crate::metrics::Timed;
+use crate::models::{escape_like, ApiError, GenericResponse, PageParams, PageQuery, StepDetails};
+use actix_web::{get, post, put, patch, delete, web, HttpResponse};
 use serde::{Deserialize, Serialize};
 use sqlx::types::{chrono, JsonValue};
-use sqlx::{self, FromRow};
+use sqlx::{self, FromRow, Postgres, QueryBuilder, Transaction};
+use utoipa::{IntoParams, ToSchema};
 
 
-#[derive(Serialize, Deserialize, FromRow)]
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Actor {
     pub actor_id: i32,
     pub first_name: String,
@@ -14,64 +16,152 @@ pub struct Actor {
     pub last_update: chrono::NaiveDateTime,
 }
 
+/// Prefix filters, case-insensitive.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ActorFilter {
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+}
+
+fn push_actor_filters(qb: &mut QueryBuilder<'_, Postgres>, filter: &ActorFilter) {
+    qb.push(" WHERE true");
+    if let Some(first_name) = &filter.first_name {
+        qb.push(" AND first_name ILIKE ").push_bind(format!("{}%", escape_like(first_name))).push(" ESCAPE '\\'");
+    }
+    if let Some(last_name) = &filter.last_name {
+        qb.push(" AND last_name ILIKE ").push_bind(format!("{}%", escape_like(last_name))).push(" ESCAPE '\\'");
+    }
+}
+
+#[utoipa::path(
+    params(ActorFilter, PageQuery),
+    responses(
+        (status = 200, description = "A page of actors", body = GenericResponse<Vec<Actor>, String>),
+        (status = 400, description = "Invalid paging or sort column", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
 #[get("")]
-pub async fn get_actors(state: web::Data<AppState>) -> impl Responder {
-    match sqlx::query_as::<_, Actor>("SELECT * FROM actor")
+pub async fn get_actors(
+    state: web::Data<AppState>,
+    page: PageParams,
+    filter: web::Query<ActorFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let mut count = QueryBuilder::new("SELECT count(*) FROM actor");
+    push_actor_filters(&mut count, &filter);
+    let total: i64 = count.build_query_scalar().fetch_one(&state.db).timed("get_actors.count").await?;
+
+    let mut query = QueryBuilder::new("SELECT * FROM actor");
+    push_actor_filters(&mut query, &filter);
+    page.push_order_by(&mut query, &["actor_id", "first_name", "last_name", "last_update"], "actor_id")?;
+    page.push_limit(&mut query);
+    let actors = query.build_query_as::<Actor>()
         .fetch_all(&state.db)
-        .await
-    {
-        Ok(actors) => HttpResponse::Ok().json(actors),
-        Err(e) => {
-            println!("{}", e);
-            HttpResponse::NotFound().json("Users not found")
-        }
-    }
+        .timed("get_actors.page")
+        .await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::paginated(actors, "Returned actors", page.pagination(total))))
+}
+
+/// Length of `actor.first_name` and `actor.last_name`.
+const MAX_NAME_LENGTH: usize = 45;
+
+fn validate_name(field: &'static str, name: &str) -> Result<(), ApiError> {
+    let message = if name.trim().is_empty() {
+        format!("{field} must not be empty")
+    } else if name.chars().count() > MAX_NAME_LENGTH {
+        format!("{field} must be at most {MAX_NAME_LENGTH} characters")
+    } else {
+        return Ok(());
+    };
+    Err(ApiError::unprocessable(message).in_step("validation", Some(field)))
 }
 
-#[derive(FromRow, Serialize, Deserialize)]
+#[derive(FromRow, Serialize, Deserialize, ToSchema)]
 pub struct ActorForm {
    pub first_name: String,
    pub last_name: String,
 }
 
+impl ActorForm {
+    fn validate(&self) -> Result<(), ApiError> {
+        validate_name("first_name", &self.first_name)?;
+        validate_name("last_name", &self.last_name)
+    }
+}
+
+/// Only the names that are present are changed.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ActorPatch {
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+}
+
+impl ActorPatch {
+    fn validate(&self) -> Result<(), ApiError> {
+        if let Some(first_name) = &self.first_name {
+            validate_name("first_name", first_name)?;
+        }
+        if let Some(last_name) = &self.last_name {
+            validate_name("last_name", last_name)?;
+        }
+        Ok(())
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The new actor", body = GenericResponse<Actor, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager or missing the catalogue:write scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 422, description = "A name is blank or too long; `data` names the field", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
 #[post("")]
-pub async fn post_actor(state: web::Data<AppState>, form: web::Json<ActorForm>) -> impl Responder {
-    match sqlx::query_as::<_, Actor>("\
+pub async fn post_actor(state: web::Data<AppState>, form: web::Json<ActorForm>) -> Result<HttpResponse, ApiError> {
+    form.validate()?;
+    let actor = sqlx::query_as::<_, Actor>("\
     INSERT INTO actor (first_name, last_name) \
     VALUES ($1,$2)\
     RETURNING *")
         .bind(&form.first_name).bind(&form.last_name)
-        .fetch_optional(&state.db)
-        .await
-    {
-        Ok(actors) => HttpResponse::Ok().json(GenericResponse::success(actors, "Successfully added new actor")),
-        Err(e) => {
-            println!("{}", e);
-            HttpResponse::BadRequest().json("Some fields are missing.")
-        }
-    }
+        .fetch_one(&state.db)
+        .timed("post_actor")
+        .await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(actor, "Successfully added new actor")))
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The actor", body = Actor),
+        (status = 404, description = "Actor not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
 #[get("/{id}")]
-pub async fn get_actor(state: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
+pub async fn get_actor(state: web::Data<AppState>, path: web::Path<i32>) -> Result<HttpResponse, ApiError> {
     let id = path.into_inner();
-    match sqlx::query_as::<_, Actor>("SELECT * FROM actor WHERE actor_id = $1 ")
+    let actor = sqlx::query_as::<_, Actor>("SELECT * FROM actor WHERE actor_id = $1 ")
         .bind(id)
-        .fetch_one(&state.db)
-        .await
-    {
-        Ok(actors) => HttpResponse::Ok().json(actors),
-        Err(e) => {
-            println!("{}", e);
-            HttpResponse::NotFound().json("Users not found")
-        }
-    }
+        .fetch_optional(&state.db)
+        .timed("get_actor")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Actor not found"))?;
+    Ok(HttpResponse::Ok().json(actor))
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated actor", body = GenericResponse<Actor, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager or missing the catalogue:write scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Actor not found", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 422, description = "A name is blank or too long; `data` names the field", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
 #[put("/{id}")]
-pub async fn update_actor(state: web::Data<AppState>, path: web::Path<i64>, form: web::Json<ActorForm>) -> impl Responder {
+pub async fn update_actor(state: web::Data<AppState>, path: web::Path<i32>, form: web::Json<ActorForm>) -> Result<HttpResponse, ApiError> {
     let id = path.into_inner();
-    match sqlx::query_as::<_, Actor>("\
+    form.validate()?;
+    let actor = sqlx::query_as::<_, Actor>("\
     UPDATE actor \
     SET \
     first_name = $1, \
@@ -81,101 +171,193 @@ pub async fn update_actor(state: web::Data<AppState>, path: web::Path<i64>, form
         .bind(&form.first_name).bind(&form.last_name)
         .bind(id)
         .fetch_optional(&state.db)
-        .await
-    {
-        Ok(actors) => HttpResponse::Ok().json(GenericResponse::success(actors, "updated actor successfully")),
-        Err(e) => {
-            println!("{}", e);
-            HttpResponse::BadRequest().json("Some fields are missing.")
-        }
-    }
+        .timed("update_actor")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Actor not found"))?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(actor, "updated actor successfully")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated actor", body = GenericResponse<Actor, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager or missing the catalogue:write scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Actor not found", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 422, description = "A given name is blank or too long; `data` names the field", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[patch("/{id}")]
+pub async fn patch_actor(state: web::Data<AppState>, path: web::Path<i32>, form: web::Json<ActorPatch>) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    form.validate()?;
+    let actor = sqlx::query_as::<_, Actor>("
+    UPDATE actor
+    SET first_name = coalesce($1, first_name), last_name = coalesce($2, last_name)
+    WHERE actor_id = $3
+    RETURNING *")
+        .bind(&form.first_name).bind(&form.last_name)
+        .bind(id)
+        .fetch_optional(&state.db)
+        .timed("patch_actor")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Actor not found"))?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(actor, "updated actor successfully")))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteActorQuery {
+    /// Also remove the actor from the cast of every film they appear in.
+    /// Without it, deleting an actor who still has films is refused.
+    #[serde(default)]
+    pub cascade: bool,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ActorFilm {
+    film_id: i32,
+    title: String,
+}
+
+async fn lock_actor(tx: &mut Transaction<'_, Postgres>, actor_id: i32) -> Result<(), ApiError> {
+    sqlx::query("SELECT actor_id FROM actor WHERE actor_id = $1 FOR UPDATE")
+        .bind(actor_id)
+        .fetch_optional(&mut **tx)
+        .timed("lock_actor")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Actor not found"))?;
+    Ok(())
 }
 
+#[utoipa::path(
+    params(DeleteActorQuery),
+    responses(
+        (status = 200, description = "Actor deleted, with the films they were removed from", body = GenericResponse<Vec<ActorFilm>, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager or missing the catalogue:write scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Actor not found", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 409, description = "The actor still appears in the films in `data`; retry with `cascade=true`", body = GenericResponse<Vec<ActorFilm>, String>),
+    ),
+)]
 #[delete("/{id}")]
-pub async fn delete_actor(state: web::Data<AppState>, path: web::Path<i64>) -> impl Responder {
+pub async fn delete_actor(
+    state: web::Data<AppState>,
+    path: web::Path<i32>,
+    query: web::Query<DeleteActorQuery>,
+) -> Result<HttpResponse, ApiError> {
     let id = path.into_inner();
-    let query = sqlx::query("DELETE FROM actor WHERE actor_id = $1")
-        .bind(&id.clone())
-        .execute(&state.db)
-        .await;
-    match query {
-        Ok(_) => HttpResponse::Ok().json(GenericResponse::success((), "success: ".to_owned() + id.to_string().as_str())),
-        Err(e) => {
-            println!("{e}");
-            HttpResponse::BadRequest().json(GenericResponse::error((), "error while deleting user: ".to_owned() + id.to_string().as_str()))
-        }
+    let mut tx = state.db.begin().timed("delete_actor.begin").await?;
+    lock_actor(&mut tx, id).await?;
+
+    let films = sqlx::query_as::<_, ActorFilm>("
+    SELECT f.film_id, f.title
+    FROM film_actor fa
+    JOIN film f
+        ON f.film_id = fa.film_id
+    WHERE fa.actor_id = $1
+    ORDER BY f.title")
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .timed("delete_actor.films")
+        .await?;
+    if !films.is_empty() && !query.cascade {
+        // Not an `ApiError`: the client needs the film ids, which don't fit `StepDetails`.
+        return Ok(HttpResponse::Conflict().json(GenericResponse::error(films, "Actor still appears in films")));
     }
-}
 
+    sqlx::query("DELETE FROM film_actor WHERE actor_id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .timed("delete_actor.film_actor")
+        .await?;
+    sqlx::query("DELETE FROM actor WHERE actor_id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .timed("delete_actor.actor")
+        .await?;
+    tx.commit().timed("delete_actor.commit").await?;
 
-#[derive(Deserialize)]
+    Ok(HttpResponse::Ok().json(GenericResponse::success(films, format!("Deleted actor {id}"))))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ActorQuery {
     pub first_name: String,
     pub last_name: String,
 }
 
+#[utoipa::path(
+    params(ActorQuery),
+    responses(
+        (status = 200, description = "The actor with exactly this name", body = Actor),
+        (status = 400, description = "A name is missing", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Actor not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
 #[get("/actor-query")]
 pub async fn get_actor_query(
     state: web::Data<AppState>,
     query: web::Query<ActorQuery>,
-) -> impl Responder {
-    match sqlx::query_as::<_, Actor>(
+) -> Result<HttpResponse, ApiError> {
+    let actor = sqlx::query_as::<_, Actor>(
         "\
     SELECT * FROM actor \
-    WHERE first_name = $1\
-    AND last_name = $2\
+    WHERE first_name = $1 \
+    AND last_name = $2 \
     ",
     )
     .bind(&query.first_name)
     .bind(&query.last_name)
-    .fetch_one(&state.db)
-    .await
-    {
-        Ok(actors) => HttpResponse::Ok().json(actors),
-        Err(e) => {
-            println!("{}", e);
-            HttpResponse::NotFound().json("User not found")
-        }
-    }
+    .fetch_optional(&state.db)
+    .timed("get_actor_query")
+    .await?
+    .ok_or_else(|| ApiError::not_found("Actor not found"))?;
+    Ok(HttpResponse::Ok().json(actor))
 }
 
-#[derive(FromRow, Deserialize, Serialize)]
+#[derive(FromRow, Deserialize, Serialize, ToSchema)]
 struct ActorFilmsByCategory {
     first_name: String,
     last_name: String,
+    /// Titles of the actor's films in the category.
+    #[schema(value_type = Vec<String>)]
     titles: JsonValue,
 }
 
-#[get("actor-film-in-category/{actor_id}/{category_id}")]
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The actor's films in the category", body = ActorFilmsByCategory),
+        (status = 404, description = "Actor not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/actor-film-in-category/{actor_id}/{category_id}")]
 pub async fn get_actor_films_by_category(
     state: web::Data<AppState>,
     path: web::Path<(i32, i32)>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let (actor_id, category_id) = path.into_inner();
-    match sqlx::query_as::<_, ActorFilmsByCategory>(
+    let actor = sqlx::query_as::<_, ActorFilmsByCategory>(
         "\
     SELECT * FROM get_actor_film_in_category($1, $2)
     ",
     )
     .bind(actor_id)
     .bind(category_id)
-    .fetch_one(&state.db)
-    .await
-    {
-        Ok(actor) => HttpResponse::Ok().json(actor),
-        Err(e) => {
-            println!("{e}");
-            HttpResponse::NotFound().body("Not found")
-        }
-    }
+    .fetch_optional(&state.db)
+    .timed("get_actor_films_by_category")
+    .await?
+    .ok_or_else(|| ApiError::not_found("Actor not found"))?;
+    Ok(HttpResponse::Ok().json(actor))
 }
 
-pub fn routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_actor_query)
-        .service(get_actors)
-        .service(get_actor)
-        .service(post_actor)
-        .service(update_actor)
-        .service(delete_actor)
-        .service(get_actor_films_by_category);
-}
+documented_routes!(routes, ActorsApi, [
+    get_actor_query,
+    get_actors,
+    get_actor,
+    post_actor,
+    update_actor,
+    patch_actor,
+    delete_actor,
+    get_actor_films_by_category,
+]);
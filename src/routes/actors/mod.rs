@@ -1,2 +1,3 @@
 pub mod actors;
-pub use actors::routes;
+
+pub use actors::{routes, ActorsApi};
\ No newline at end of file
@@ -0,0 +1,128 @@
+use crate::AppState;
+use crate::auth::{api_key, ApiScope, AuthenticatedStaff, Role};
+use crate::metrics::Timed;
+use crate::models::{ApiError, GenericResponse, StepDetails};
+
+use actix_web::{delete, get, post, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::{self, FromRow};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ApiKey {
+    api_key_id: i32,
+    name: String,
+    prefix: String,
+    scopes: Vec<String>,
+    created_by: Option<i32>,
+    created_at: chrono::NaiveDateTime,
+    last_used_at: Option<chrono::NaiveDateTime>,
+    revoked_at: Option<chrono::NaiveDateTime>,
+}
+
+const API_KEY_COLUMNS: &str = "api_key_id, name, prefix, scopes, created_by, created_at, last_used_at, revoked_at";
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Every API key, revoked ones included", body = GenericResponse<Vec<ApiKey>, String>),
+        (status = 401, description = "Not logged in", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("")]
+pub async fn get_api_keys(state: web::Data<AppState>, staff: AuthenticatedStaff) -> Result<HttpResponse, ApiError> {
+    staff.require(Role::Manager)?;
+    let keys = sqlx::query_as::<_, ApiKey>(&format!("SELECT {API_KEY_COLUMNS} FROM api_key ORDER BY api_key_id"))
+        .fetch_all(&state.db)
+        .timed("get_api_keys")
+        .await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(keys, "Returned API keys")))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ApiKeyForm {
+    name: String,
+    scopes: Vec<ApiScope>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreatedApiKey {
+    /// The plaintext key. Only its hash is stored, so this is the only time it is shown.
+    key: String,
+    #[serde(flatten)]
+    api_key: ApiKey,
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The new key, including its plaintext", body = GenericResponse<CreatedApiKey, String>),
+        (status = 400, description = "Blank name or no scopes", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 401, description = "Not logged in", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("")]
+pub async fn create_api_key(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    form: web::Json<ApiKeyForm>,
+) -> Result<HttpResponse, ApiError> {
+    staff.require(Role::Manager)?;
+    if form.name.trim().is_empty() {
+        return Err(ApiError::bad_request("name must not be empty"));
+    }
+    if form.scopes.is_empty() {
+        return Err(ApiError::bad_request("scopes must not be empty"));
+    }
+    let mut scopes: Vec<&str> = form.scopes.iter().map(|scope| scope.as_str()).collect();
+    scopes.sort();
+    scopes.dedup();
+
+    let generated = api_key::generate();
+    let api_key = sqlx::query_as::<_, ApiKey>(&format!("
+    INSERT INTO api_key (name, prefix, key_hash, scopes, created_by)
+    VALUES ($1, $2, $3, $4, $5)
+    RETURNING {API_KEY_COLUMNS}
+    "))
+        .bind(form.name.trim())
+        .bind(&generated.prefix)
+        .bind(&generated.hash)
+        .bind(scopes)
+        .bind(staff.staff_id)
+        .fetch_one(&state.db)
+        .timed("create_api_key")
+        .await?;
+    let created = CreatedApiKey { key: generated.key, api_key };
+    Ok(HttpResponse::Ok().json(GenericResponse::success(created, "Successfully created API key")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The revoked key", body = GenericResponse<ApiKey, String>),
+        (status = 401, description = "Not logged in", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "API key not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[delete("/{api_key_id}")]
+pub async fn revoke_api_key(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    staff.require(Role::Manager)?;
+    let api_key = sqlx::query_as::<_, ApiKey>(&format!("
+    UPDATE api_key
+    SET revoked_at = coalesce(revoked_at, now())
+    WHERE api_key_id = $1
+    RETURNING {API_KEY_COLUMNS}
+    "))
+        .bind(path.into_inner())
+        .fetch_optional(&state.db)
+        .timed("revoke_api_key")
+        .await?
+        .ok_or_else(|| ApiError::not_found("API key not found"))?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(api_key, "Revoked API key")))
+}
+
+documented_routes!(routes, ApiKeysApi, [get_api_keys, create_api_key, revoke_api_key]);
@@ -0,0 +1,3 @@
+pub mod api_keys;
+
+pub use api_keys::{routes, ApiKeysApi};
\ No newline at end of file
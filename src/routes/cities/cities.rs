@@ -1,9 +1,12 @@
 use crate::AppState;
-use actix_web::{get, web, HttpResponse, Responder, post};
+use crate::metrics::Timed;
+use crate::models::{escape_like, ApiError, GenericResponse, PageParams, PageQuery, StepDetails};
+use actix_web::{get, web, HttpResponse, post};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{FromRow, Postgres, QueryBuilder};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Serialize, Deserialize, FromRow)]
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
 pub struct City {
     pub city_id: i32,
     pub city: String,
@@ -11,65 +14,99 @@ pub struct City {
     pub last_update: chrono::NaiveDateTime,
 }
 
-#[get("")]
-pub async fn get_cities(state: web::Data<AppState>) -> impl Responder {
-    match sqlx::query_as::<_, City>("SELECT * FROM city")
-        .fetch_all(&state.db)
-        .await
-    {
-        Ok(cities) => HttpResponse::Ok().json(cities),
-        Err(e) => {
-            println!("{e}");
-            HttpResponse::NotFound().body("Cities not found")
-        }
+const CITY_SORT_COLUMNS: &[&str] = &["city_id", "city", "country_id", "last_update"];
+
+/// `city` is a case-insensitive prefix.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CityFilter {
+    pub city: Option<String>,
+    pub country_id: Option<i16>,
+}
+
+fn push_city_filters(qb: &mut QueryBuilder<'_, Postgres>, filter: &CityFilter) {
+    qb.push(" WHERE true");
+    if let Some(city) = &filter.city {
+        qb.push(" AND city ILIKE ").push_bind(format!("{}%", escape_like(city))).push(" ESCAPE '\\'");
     }
+    if let Some(country_id) = filter.country_id {
+        qb.push(" AND country_id = ").push_bind(country_id);
+    }
+}
+
+async fn fetch_cities_page(
+    state: &AppState,
+    page: &PageParams,
+    filter: &CityFilter,
+) -> Result<HttpResponse, ApiError> {
+    let mut count = QueryBuilder::new("SELECT count(*) FROM city");
+    push_city_filters(&mut count, filter);
+    let total: i64 = count.build_query_scalar().fetch_one(&state.db).timed("fetch_cities_page.count").await?;
+
+    let mut query = QueryBuilder::new("SELECT * FROM city");
+    push_city_filters(&mut query, filter);
+    page.push_order_by(&mut query, CITY_SORT_COLUMNS, "city_id")?;
+    page.push_limit(&mut query);
+    let cities = query.build_query_as::<City>()
+        .fetch_all(&state.db)
+        .timed("fetch_cities_page.page")
+        .await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::paginated(cities, "Returned cities", page.pagination(total))))
+}
+
+#[utoipa::path(
+    params(CityFilter, PageQuery),
+    responses(
+        (status = 200, description = "A page of cities", body = GenericResponse<Vec<City>, String>),
+        (status = 400, description = "Invalid paging, filter or sort column", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("")]
+pub async fn get_cities(
+    state: web::Data<AppState>,
+    page: PageParams,
+    filter: web::Query<CityFilter>,
+) -> Result<HttpResponse, ApiError> {
+    fetch_cities_page(&state, &page, &filter).await
 }
 
+#[utoipa::path(
+    params(CityFilter, PageQuery),
+    responses(
+        (status = 200, description = "A page of the country's cities", body = GenericResponse<Vec<City>, String>),
+        (status = 400, description = "Invalid paging, filter or sort column", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
 #[get("/{country_id}")]
 pub async fn get_cities_by_country(
     state: web::Data<AppState>,
     path: web::Path<i16>,
-) -> impl Responder {
-    let country_id = path.into_inner();
-    match sqlx::query_as::<_, City>(
-        "SELECT * FROM city WHERE country_id = $1",
-    )
-    .bind(country_id)
-    .fetch_all(&state.db)
-    .await
-    {
-        Ok(cities) => HttpResponse::Ok().json(cities),
-        Err(e) => {
-            println!("{e}");
-            HttpResponse::NotFound().body("Cities not found")
-        }
-    }
+    page: PageParams,
+    filter: web::Query<CityFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let filter = CityFilter {
+        country_id: Some(path.into_inner()),
+        ..filter.into_inner()
+    };
+    fetch_cities_page(&state, &page, &filter).await
 }
 
 #[post("")]
 pub async fn post_city(
     state: web::Data<AppState>,
     city: web::Json<City>,
-) -> impl Responder {
-    match sqlx::query(
+) -> Result<HttpResponse, ApiError> {
+    sqlx::query(
         "INSERT INTO city (city, country_id, last_update) VALUES ($1, $2, $3)",
     )
     .bind(&city.city)
-    .bind(&city.country_id)
-    .bind(&city.last_update)
+    .bind(city.country_id)
+    .bind(city.last_update)
     .execute(&state.db)
-    .await
-    {
-        Ok(_) => HttpResponse::Ok().body("City added successfully"),
-        Err(e) => {
-            println!("{e}");
-            HttpResponse::NotFound().body("City not added")
-        }
-    }
+    .timed("post_city")
+    .await?;
+    Ok(HttpResponse::Ok().body("City added successfully"))
 }
 
 
-pub fn routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_cities);
-    cfg.service(get_cities_by_country);
-}
+documented_routes!(routes, CitiesApi, [get_cities, get_cities_by_country]);
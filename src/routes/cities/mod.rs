@@ -1,2 +1,3 @@
 pub mod cities;
-pub use cities::routes;
+
+pub use cities::{routes, CitiesApi};
\ No newline at end of file
@@ -1,101 +1,192 @@
 use crate::AppState;
-use actix_web::{get, web, HttpResponse, Responder};
-use serde::Deserialize;
+use crate::metrics::Timed;
+use crate::models::{ApiError, GenericResponse, StepDetails};
 
-#[derive(Deserialize)]
-struct CounterInfo {
+use actix_web::{get, post, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::{self, FromRow};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CounterInfo {
     amount: i32,
     multiplier: i32,
 }
 
-#[get("/add")]
-async fn add_counter(state: web::Data<AppState>) -> impl Responder {
-    let mut counter = state.counter.lock().unwrap();
-    *counter += 1;
-    HttpResponse::Ok().body(counter.to_string())
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Counter {
+    name: String,
+    value: i32,
+    last_update: chrono::NaiveDateTime,
+}
+
+/// `amount * multiplier`, negated when subtracting. Overflow is the caller's
+/// input being out of range rather than a server fault.
+fn delta(amount: i32, multiplier: i32, subtract: bool) -> Result<i32, ApiError> {
+    amount
+        .checked_mul(multiplier)
+        .and_then(|delta| if subtract { delta.checked_neg() } else { Some(delta) })
+        .ok_or_else(|| ApiError::unprocessable("Value out of range"))
 }
 
-#[get("/add-query")]
-async fn add_counter_amount_query(
+/// Adds `delta` in a single statement, creating the counter at zero first if
+/// needed. An overflowing total fails in Postgres and becomes a 422 as well.
+async fn apply(state: &AppState, name: &str, delta: i32) -> Result<HttpResponse, ApiError> {
+    let counter = sqlx::query_as::<_, Counter>("
+    INSERT INTO counter (name, value)
+    VALUES ($1, $2)
+    ON CONFLICT (name) DO UPDATE
+    SET value = counter.value + EXCLUDED.value, last_update = now()
+    RETURNING name, value, last_update
+    ")
+        .bind(name)
+        .bind(delta)
+        .fetch_one(&state.db)
+        .timed("update_counter")
+        .await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(counter, "Updated counter")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The counter's current value", body = GenericResponse<Counter, String>),
+        (status = 404, description = "No counter by that name has been changed yet", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/{name}")]
+pub async fn get_counter(state: web::Data<AppState>, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    let counter = sqlx::query_as::<_, Counter>("SELECT name, value, last_update FROM counter WHERE name = $1")
+        .bind(path.into_inner())
+        .fetch_optional(&state.db)
+        .timed("get_counter")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Counter not found"))?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(counter, "Returned counter")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The counter after adding one", body = GenericResponse<Counter, String>),
+        (status = 422, description = "The counter would overflow", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{name}/add")]
+pub async fn add_counter(state: web::Data<AppState>, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    apply(&state, &path.into_inner(), 1).await
+}
+
+#[utoipa::path(
+    params(CounterInfo),
+    responses(
+        (status = 200, description = "The counter after adding `amount * multiplier`", body = GenericResponse<Counter, String>),
+        (status = 422, description = "The change or the counter would overflow", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{name}/add-query")]
+pub async fn add_counter_amount_query(
     state: web::Data<AppState>,
-    path: web::Query<CounterInfo>,
-) -> impl Responder {
-    let path = path.into_inner();
-    let mut counter = state.counter.lock().unwrap();
-    *counter += path.amount * path.multiplier;
-    HttpResponse::Ok().body(counter.to_string())
+    path: web::Path<String>,
+    query: web::Query<CounterInfo>,
+) -> Result<HttpResponse, ApiError> {
+    apply(&state, &path.into_inner(), delta(query.amount, query.multiplier, false)?).await
 }
 
-#[get("/add/{amount}")]
-async fn add_counter_amount(state: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
-    let path = path.into_inner();
-    let mut counter = state.counter.lock().unwrap();
-    let amount = path;
-    *counter += amount;
-    HttpResponse::Ok().body(counter.to_string())
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The counter after adding `amount`", body = GenericResponse<Counter, String>),
+        (status = 422, description = "The counter would overflow", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{name}/add/{amount}")]
+pub async fn add_counter_amount(
+    state: web::Data<AppState>,
+    path: web::Path<(String, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (name, amount) = path.into_inner();
+    apply(&state, &name, amount).await
 }
 
-#[get("/add/{amount}/{multiplier}")]
-async fn add_counter_amount_multi(
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The counter after adding `amount * multiplier`", body = GenericResponse<Counter, String>),
+        (status = 422, description = "The change or the counter would overflow", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{name}/add/{amount}/{multiplier}")]
+pub async fn add_counter_amount_multi(
     state: web::Data<AppState>,
-    path: web::Path<(i32, i32)>,
-) -> impl Responder {
-    // let path = path.into_inner();
-    let (amount, multiplier) = path.into_inner();
-    let mut counter = state.counter.lock().unwrap();
-    *counter += amount * multiplier;
-    HttpResponse::Ok().body(counter.to_string())
+    path: web::Path<(String, i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (name, amount, multiplier) = path.into_inner();
+    apply(&state, &name, delta(amount, multiplier, false)?).await
 }
 
-#[get("/minus")]
-pub async fn minus_counter(state: web::Data<AppState>) -> impl Responder {
-    let mut counter = state.counter.lock().unwrap();
-    *counter -= 1;
-    HttpResponse::Ok().body(counter.to_string())
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The counter after subtracting one", body = GenericResponse<Counter, String>),
+        (status = 422, description = "The counter would overflow", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{name}/minus")]
+pub async fn minus_counter(state: web::Data<AppState>, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    apply(&state, &path.into_inner(), -1).await
 }
 
-#[get("/minus-query")]
-async fn minus_counter_amount_query(
+#[utoipa::path(
+    params(CounterInfo),
+    responses(
+        (status = 200, description = "The counter after subtracting `amount * multiplier`", body = GenericResponse<Counter, String>),
+        (status = 422, description = "The change or the counter would overflow", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{name}/minus-query")]
+pub async fn minus_counter_amount_query(
     state: web::Data<AppState>,
-    path: web::Query<CounterInfo>,
-) -> impl Responder {
-    let path = path.into_inner();
-    let mut counter = state.counter.lock().unwrap();
-    *counter -= path.amount * path.multiplier;
-    HttpResponse::Ok().body(counter.to_string())
+    path: web::Path<String>,
+    query: web::Query<CounterInfo>,
+) -> Result<HttpResponse, ApiError> {
+    apply(&state, &path.into_inner(), delta(query.amount, query.multiplier, true)?).await
 }
 
-#[get("/minus/{amount}")]
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The counter after subtracting `amount`", body = GenericResponse<Counter, String>),
+        (status = 422, description = "The change or the counter would overflow", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{name}/minus/{amount}")]
 pub async fn minus_counter_amount(
     state: web::Data<AppState>,
-    path: web::Path<i32>,
-) -> impl Responder {
-    let path = path.into_inner();
-    let mut counter = state.counter.lock().unwrap();
-    let amount = path;
-    *counter -= amount;
-    HttpResponse::Ok().body(counter.to_string())
+    path: web::Path<(String, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (name, amount) = path.into_inner();
+    apply(&state, &name, delta(amount, 1, true)?).await
 }
 
-#[get("/minus/{amount}/{multiplier}")]
-async fn minus_counter_amount_multi(
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The counter after subtracting `amount * multiplier`", body = GenericResponse<Counter, String>),
+        (status = 422, description = "The change or the counter would overflow", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{name}/minus/{amount}/{multiplier}")]
+pub async fn minus_counter_amount_multi(
     state: web::Data<AppState>,
-    path: web::Path<(i32, i32)>,
-) -> impl Responder {
-    // let path = path.into_inner();
-    let (amount, multiplier) = path.into_inner();
-    let mut counter = state.counter.lock().unwrap();
-    *counter += amount * multiplier;
-    HttpResponse::Ok().body(counter.to_string())
+    path: web::Path<(String, i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (name, amount, multiplier) = path.into_inner();
+    apply(&state, &name, delta(amount, multiplier, true)?).await
 }
 
-pub fn counter_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(add_counter)
-        .service(add_counter_amount)
-        .service(add_counter_amount_multi)
-        .service(add_counter_amount_query)
-        .service(minus_counter)
-        .service(minus_counter_amount)
-        .service(minus_counter_amount_multi)
-        .service(minus_counter_amount_query);
-}
+documented_routes!(counter_routes, CounterApi, [
+    get_counter,
+    add_counter,
+    add_counter_amount_query,
+    add_counter_amount,
+    add_counter_amount_multi,
+    minus_counter,
+    minus_counter_amount_query,
+    minus_counter_amount,
+    minus_counter_amount_multi,
+]);
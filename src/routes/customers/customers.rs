@@ -1,20 +1,29 @@
 use crate::AppState;
-use crate::models::GenericResponse;
+use crate::auth::{AuthenticatedStaff, Role};
+use crate::metrics::Timed;
+use crate::models::{escape_like, ApiError, GenericResponse, PageParams, PageQuery, StepDetails};
+use super::{rentals, search};
 
-use actix_web::{get, web, HttpResponse, Responder, post};
+use actix_web::{get, web, HttpResponse, post, put};
 use chrono;
 use serde::{Deserialize, Serialize};
-use sqlx::{self, FromRow};
+use sqlx::{self, FromRow, PgPool, Postgres, QueryBuilder, Transaction};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Deserialize, Serialize, FromRow)]
+#[derive(Deserialize, Serialize, FromRow, ToSchema)]
 pub struct TotalCustomersPerShop {
     count: Option<i64>,
     address: String,
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Customer count per store address", body = GenericResponse<Vec<TotalCustomersPerShop>, String>),
+    ),
+)]
 #[get("/total_per_shop")]
-pub async fn get_total_customers_per_shop(state: web::Data<AppState>) -> impl Responder {
-    match sqlx::query_as!(TotalCustomersPerShop, "
+pub async fn get_total_customers_per_shop(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let customers = sqlx::query_as!(TotalCustomersPerShop, "
     SELECT count(*) as count, t3.address
     FROM customer t1
     JOIN store t2
@@ -23,19 +32,12 @@ pub async fn get_total_customers_per_shop(state: web::Data<AppState>) -> impl Re
         ON t2.address_id = t3.address_id
     GROUP BY t1.store_id, t3.address
     ORDER BY count DESC;
-    ").fetch_all(&state.db).await
-    {
-        Ok(customers) => HttpResponse::Ok()
-            .json(GenericResponse::success(customers, "Returned customers per shop")),
-        Err(e) => {
-            println!("{e}");
-            HttpResponse::NotFound()
-                .json(GenericResponse::error((), "Didn't find any customers"))
-        }
-    }
+    ").fetch_all(&state.db).timed("get_total_customers_per_shop").await?;
+    Ok(HttpResponse::Ok()
+        .json(GenericResponse::success(customers, "Returned customers per shop")))
 }
 
-#[derive(Serialize, Deserialize, FromRow)]
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
 pub struct CustomersInShop {
     first_name: String,
     last_name: String,
@@ -45,27 +47,69 @@ pub struct CustomersInShop {
     last_update: Option<chrono::NaiveDateTime>,
 }
 
+/// `last_name` is a case-insensitive prefix, `email` a case-insensitive substring.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CustomerFilter {
+    pub last_name: Option<String>,
+    pub email: Option<String>,
+    pub active: Option<bool>,
+}
+
+fn push_customer_filters(qb: &mut QueryBuilder<'_, Postgres>, store_id: i16, filter: &CustomerFilter) {
+    qb.push(" WHERE store_id = ").push_bind(store_id);
+    if let Some(last_name) = &filter.last_name {
+        qb.push(" AND last_name ILIKE ").push_bind(format!("{}%", escape_like(last_name))).push(" ESCAPE '\\'");
+    }
+    if let Some(email) = &filter.email {
+        qb.push(" AND email ILIKE ").push_bind(format!("%{}%", escape_like(email))).push(" ESCAPE '\\'");
+    }
+    if let Some(active) = filter.active {
+        qb.push(" AND activebool = ").push_bind(active);
+    }
+}
+
+#[utoipa::path(
+    params(CustomerFilter, PageQuery),
+    responses(
+        (status = 200, description = "A page of the store's customers", body = GenericResponse<Vec<CustomersInShop>, String>),
+        (status = 400, description = "Invalid paging, filter or sort column", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
 #[get("/shop/{shop_id}")]
-pub async fn get_customers_from_shop(state: web::Data<AppState>, path: web::Path<i16>) -> impl Responder {
+pub async fn get_customers_from_shop(
+    state: web::Data<AppState>,
+    path: web::Path<i16>,
+    page: PageParams,
+    filter: web::Query<CustomerFilter>,
+) -> Result<HttpResponse, ApiError> {
     let id = path.into_inner();
-    match sqlx::query_as!(CustomersInShop, "
+    let mut count = QueryBuilder::new("SELECT count(*) FROM customer");
+    push_customer_filters(&mut count, id, &filter);
+    let total: i64 = count.build_query_scalar().fetch_one(&state.db).timed("get_customers_from_shop.count").await?;
+
+    let mut query = QueryBuilder::new("
     SELECT first_name, last_name, email, activebool, create_date, last_update
-    FROM customer
-    WHERE store_id = $1", id)
+    FROM customer");
+    push_customer_filters(&mut query, id, &filter);
+    page.push_order_by(
+        &mut query,
+        &["customer_id", "first_name", "last_name", "email", "create_date", "last_update"],
+        "customer_id",
+    )?;
+    page.push_limit(&mut query);
+    let customers = query.build_query_as::<CustomersInShop>()
         .fetch_all(&state.db)
-        .await {
-        Ok(customers) => HttpResponse::Ok()
-            .json(GenericResponse::success(customers, "Returned customers for a single shop")),
-        Err(e) => {
-            println!("{e}");
-            HttpResponse::NotFound()
-                .json(GenericResponse::error((), "Didn't find any customers"))
-        }
-    }
+        .timed("get_customers_from_shop.page")
+        .await?;
+    Ok(HttpResponse::Ok()
+        .json(GenericResponse::paginated(customers, "Returned customers for a single shop", page.pagination(total))))
 }
 
-#[derive(Serialize, Deserialize, FromRow)]
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
 pub struct CustomerDetails {
+    customer_id: i32,
+    store_id: i16,
     first_name: String,
     last_name: String,
     email: Option<String>,
@@ -77,33 +121,42 @@ pub struct CustomerDetails {
     phone: String,
     postal_code: Option<String>,
     city: String,
+    /// When the customer's personal data was erased; `null` for current customers.
+    erased_at: Option<chrono::NaiveDateTime>,
 }
 
-#[get("/{customer_id}")]
-pub async fn get_customer_details(state: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
-    let customer_id = path.into_inner();
-    match sqlx::query_as!(CustomerDetails, "\
-    SELECT t1.first_name, t1.last_name, t1.email, t1.activebool, t1.create_date, t1.last_update,
+pub async fn fetch_customer_details(db: &PgPool, customer_id: i32) -> Result<CustomerDetails, ApiError> {
+    sqlx::query_as!(CustomerDetails, "\
+    SELECT t1.customer_id, t1.store_id, t1.first_name, t1.last_name, t1.email, t1.activebool,
+       t1.create_date, t1.last_update,
        t2.address, t2.district, t2.phone, t2.postal_code,
-       t3.city
+       t3.city,
+       t1.erased_at
     FROM customer t1
     JOIN address t2
         ON t1.address_id = t2.address_id
     JOIN city t3
         ON t2.city_id = t3.city_id
     WHERE customer_id = $1", customer_id)
-        .fetch_one(&state.db)
-        .await {
-        Ok(customer) => HttpResponse::Ok().json(GenericResponse::success(customer, "Returned customer details")),
-        Err(e) => {
-            println!("{e}");
-            HttpResponse::NotFound()
-                .json(GenericResponse::error((), "Customer not found"))
-        }
-    }
+        .fetch_optional(db)
+        .timed("fetch_customer_details")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Customer not found"))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The customer with their address", body = GenericResponse<CustomerDetails, String>),
+        (status = 404, description = "Customer not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/{customer_id}")]
+pub async fn get_customer_details(state: web::Data<AppState>, path: web::Path<i32>) -> Result<HttpResponse, ApiError> {
+    let customer = fetch_customer_details(&state.db, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(customer, "Returned customer details")))
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 pub struct CreateCustomerForm {
     store_id: i16,
     first_name: String,
@@ -113,7 +166,7 @@ pub struct CreateCustomerForm {
     address: CreateAddress,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 pub struct CreateCustomer {
     customer_id: Option<i32>,
     store_id: i16,
@@ -125,7 +178,8 @@ pub struct CreateCustomer {
     address_id: i16,
 }
 
-#[derive(Deserialize, Serialize)]
+/// City and country are looked up by name and created when missing.
+#[derive(Deserialize, Serialize, ToSchema)]
 pub struct CreateAddress {
     address: String,
     address2: Option<String>,
@@ -152,91 +206,214 @@ pub struct AddressRespond {
     pub address_id: i32,
 }
 
-#[post("")]
-pub async fn create_customer(state: web::Data<AppState>, data: web::Json<CreateCustomerForm>) -> impl Responder {
-    let mut tx: sqlx::Transaction<'_, sqlx::Postgres> = state.db.begin().await.expect("failed to start transaction");
+/// Checks the fields the create and update forms share, tagging the first bad one.
+fn validate_customer(
+    first_name: &str,
+    last_name: &str,
+    email: Option<&str>,
+    address: &CreateAddress,
+) -> Result<(), ApiError> {
+    let required = [
+        ("first_name", first_name),
+        ("last_name", last_name),
+        ("address.address", &address.address),
+        ("address.district", &address.district),
+        ("address.phone", &address.phone),
+        ("address.city", &address.city),
+        ("address.country", &address.country),
+    ];
+    for (field, value) in required {
+        if value.trim().is_empty() {
+            return Err(ApiError::unprocessable(format!("{field} must not be empty"))
+                .in_step("validation", Some(field)));
+        }
+    }
+    if let Some(email) = email {
+        if !email.contains('@') {
+            return Err(ApiError::unprocessable("email is not a valid address")
+                .in_step("validation", Some("email")));
+        }
+    }
+    Ok(())
+}
+
+impl CreateCustomerForm {
+    fn validate(&self) -> Result<(), ApiError> {
+        validate_customer(&self.first_name, &self.last_name, self.email.as_deref(), &self.address)
+    }
+}
+
+async fn require_store_exists(tx: &mut Transaction<'_, Postgres>, store_id: i16) -> Result<(), ApiError> {
+    let store_exists = sqlx::query_as!(
+        ValueExists,
+        "SELECT exists(select * from store where store.store_id = $1)",
+        store_id as i32)
+        .fetch_one(&mut **tx).timed("require_store_exists").await.map_err(ApiError::step("store", Some("store_id")))?;
+    if store_exists.exists != Some(true) {
+        return Err(ApiError::unprocessable("Store does not exist").in_step("store", Some("store_id")));
+    }
+    Ok(())
+}
+
+/// Emails are unique case-insensitively; `customer_id` is the customer keeping theirs on update.
+async fn require_email_free(
+    tx: &mut Transaction<'_, Postgres>,
+    email: &str,
+    customer_id: Option<i32>,
+) -> Result<(), ApiError> {
+    let email_taken = sqlx::query_as!(
+        ValueExists,
+        "SELECT exists(select * from customer where lower(customer.email) = lower($1) and customer_id IS DISTINCT FROM $2)",
+        email,
+        customer_id)
+        .fetch_one(&mut **tx).timed("require_email_free").await.map_err(ApiError::step("email", Some("email")))?;
+    if email_taken.exists == Some(true) {
+        return Err(ApiError::conflict("Email is already in use").in_step("email", Some("email")));
+    }
+    Ok(())
+}
+
+/// Pagila's serial ids are referenced through `smallint` columns, so a row past
+/// 32767 can't be pointed at without truncating its id.
+fn smallint_id(id: i32, table: &str) -> Result<i16, ApiError> {
+    i16::try_from(id).map_err(|_| ApiError::Internal(format!("{table} id {id} does not fit a smallint column")))
+}
+
+/// Returns the id of the address's city, creating the country and city when missing.
+async fn upsert_city(tx: &mut Transaction<'_, Postgres>, address: &CreateAddress) -> Result<i16, ApiError> {
     let country_exists = sqlx::query_as!(
         ValueExists,
         "SELECT exists(select * from country where country.country = $1)",
-        &data.address.country.clone())
-        .fetch_one(&mut *tx).await.expect("Error while checking country");
+        &address.country)
+        .fetch_one(&mut **tx).timed("upsert_city.country_exists").await.map_err(ApiError::step("country", Some("address.country")))?;
 
-    let country_respond: CountryRespond;
-
-    if country_exists.exists == Some(true) {
-        country_respond = sqlx::query_as!(
+    let country_respond = if country_exists.exists == Some(true) {
+        sqlx::query_as!(
             CountryRespond,
             "SELECT t1.country_id FROM country t1 WHERE t1.country = $1",
-            data.address.country.clone()
-        ).fetch_one(&mut *tx).await.expect("Error while Inserting");
+            &address.country
+        ).fetch_one(&mut **tx).timed("upsert_city.country_id").await.map_err(ApiError::step("country", Some("address.country")))?
     } else {
-        country_respond = sqlx::query_as!(
+        sqlx::query_as!(
             CountryRespond,
             "INSERT INTO country (country)\
             VALUES ($1)\
             RETURNING country_id",
-            data.address.country.clone()
-        ).fetch_one(&mut *tx).await.expect("Error while Inserting");
-    }
+            &address.country
+        ).fetch_one(&mut **tx).timed("upsert_city.insert_country").await.map_err(ApiError::step("country", Some("address.country")))?
+    };
+    let country_id = smallint_id(country_respond.country_id, "Country").map_err(|e| e.in_step("country", Some("address.country")))?;
 
-    let city_exists:ValueExists = sqlx::query_as!(
+    let city_exists = sqlx::query_as!(
         ValueExists,
         "SELECT exists(select * from city where city.city = $1 and city.country_id = $2)",
-        &data.address.city.clone(),
-        country_respond.country_id.clone() as i16
-        ).fetch_one(&mut *tx).await.expect("Error while checking existing city");
+        &address.city,
+        country_id
+        ).fetch_one(&mut **tx).timed("upsert_city.city_exists").await.map_err(ApiError::step("city", Some("address.city")))?;
 
-    let city_respond:CityRespond;
-
-    if city_exists.exists == Some(true) {
-        city_respond = sqlx::query_as!(
+    let city_respond = if city_exists.exists == Some(true) {
+        sqlx::query_as!(
             CityRespond,
             "SELECT city_id FROM city WHERE city.city = $1 and city.country_id = $2",
-            &data.address.city.clone(),
-            country_respond.country_id.clone() as i16
-        ).fetch_one(&mut *tx).await.expect("Error while Inserting");
+            &address.city,
+            country_id
+        ).fetch_one(&mut **tx).timed("upsert_city.city_id").await.map_err(ApiError::step("city", Some("address.city")))?
     } else {
-        city_respond = sqlx::query_as!(
+        sqlx::query_as!(
             CityRespond,
             "INSERT INTO city (city, country_id)\
             VALUES ($1, $2)\
             RETURNING city_id",
-            data.address.city.clone(),
-            country_respond.country_id.clone() as i16
-        ).fetch_one(&mut *tx).await.expect("Error while Inserting");
-    }
+            &address.city,
+            country_id
+        ).fetch_one(&mut **tx).timed("upsert_city.insert_city").await.map_err(ApiError::step("city", Some("address.city")))?
+    };
+    smallint_id(city_respond.city_id, "City").map_err(|e| e.in_step("city", Some("address.city")))
+}
 
+async fn insert_address(
+    tx: &mut Transaction<'_, Postgres>,
+    address: &CreateAddress,
+    city_id: i16,
+) -> Result<i16, ApiError> {
     let address_respond = sqlx::query_as!(
         AddressRespond,
         "INSERT \
         INTO address (address, address2, district, city_id, postal_code, phone)\
         VALUES ($1, $2, $3, $4, $5, $6)
         RETURNING address_id",
-        data.address.address.clone(),
-        data.address.address2.clone(),
-        data.address.district.clone(),
-        city_respond.city_id.clone() as i16,
-        data.address.postal_code.clone(),
-        data.address.phone.clone()
+        &address.address,
+        address.address2,
+        &address.district,
+        city_id,
+        address.postal_code,
+        &address.phone
     )
-        .fetch_one(&mut *tx).await.expect("Error while inserting new address");
+        .fetch_one(&mut **tx).timed("insert_address").await.map_err(ApiError::step("address", None))?;
+    smallint_id(address_respond.address_id, "Address").map_err(|e| e.in_step("address", None))
+}
+
+/// Drops an address a customer moved away from, unless a store, a staff member
+/// or another customer still lives there.
+async fn delete_unused_address(tx: &mut Transaction<'_, Postgres>, address_id: i16) -> Result<(), ApiError> {
+    sqlx::query("
+    DELETE FROM address a
+    WHERE a.address_id = $1
+    AND NOT EXISTS (SELECT 1 FROM customer c WHERE c.address_id = a.address_id)
+    AND NOT EXISTS (SELECT 1 FROM staff s WHERE s.address_id = a.address_id)
+    AND NOT EXISTS (SELECT 1 FROM store s WHERE s.address_id = a.address_id)
+    ")
+        .bind(address_id as i32)
+        .execute(&mut **tx)
+        .timed("delete_unused_address")
+        .await
+        .map_err(ApiError::step("address", None))?;
+    Ok(())
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The new customer", body = GenericResponse<CreateCustomer, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "The store is not the caller's", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 409, description = "Email is already in use", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 422, description = "A blank field, unknown store or a value the database rejects; `data` names the failed step", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("")]
+pub async fn create_customer(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    data: web::Json<CreateCustomerForm>,
+) -> Result<HttpResponse, ApiError> {
+    data.validate()?;
+    staff.require_store(data.store_id).map_err(|e| e.in_step("store", Some("store_id")))?;
+
+    let mut tx = state.db.begin().timed("create_customer.begin").await.map_err(ApiError::step("transaction", None))?;
+
+    require_store_exists(&mut tx, data.store_id).await?;
+    if let Some(email) = &data.email {
+        require_email_free(&mut tx, email, None).await?;
+    }
+    let city_id = upsert_city(&mut tx, &data.address).await?;
+    let address_id = insert_address(&mut tx, &data.address, city_id).await?;
 
     let customer = sqlx::query!("INSERT INTO customer \
         (store_id, first_name, last_name, email, address_id, activebool) \
         VALUES ($1, $2, $3, $4, $5, $6)\
         RETURNING *
         ;",
-        &data.store_id.to_owned(),
+        data.store_id,
         &data.first_name,
         &data.last_name,
         data.email,
-        address_respond.address_id as i16,
-        &data.activebool
+        address_id,
+        data.activebool
 
     )
-        .fetch_one(&mut *tx).await.expect("Error while Inserting");
+        .fetch_one(&mut *tx).timed("create_customer.customer").await.map_err(ApiError::step("customer", None))?;
 
-    tx.commit().await.expect("Transaction got rollback due to the internal error");
+    tx.commit().timed("create_customer.commit").await.map_err(ApiError::step("commit", None))?;
 
     let respond = CreateCustomer {
         customer_id: Some(customer.customer_id),
@@ -249,14 +426,246 @@ pub async fn create_customer(state: web::Data<AppState>, data: web::Json<CreateC
         active: customer.active,
     };
 
+    Ok(HttpResponse::Ok().json(GenericResponse::success(respond, "Successfully created customer")))
+}
+
+#[derive(FromRow)]
+struct LockedCustomer {
+    store_id: i16,
+    address_id: i16,
+    erased_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Locks a customer for a write. Erased customers can no longer be changed.
+async fn lock_customer(tx: &mut Transaction<'_, Postgres>, customer_id: i32) -> Result<LockedCustomer, ApiError> {
+    let customer = sqlx::query_as::<_, LockedCustomer>(
+        "SELECT store_id, address_id, erased_at FROM customer WHERE customer_id = $1 FOR UPDATE",
+    )
+        .bind(customer_id)
+        .fetch_optional(&mut **tx)
+        .timed("lock_customer")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Customer not found"))?;
+    if customer.erased_at.is_some() {
+        return Err(ApiError::conflict("Customer has been erased"));
+    }
+    Ok(customer)
+}
+
+async fn require_no_open_rentals(tx: &mut Transaction<'_, Postgres>, customer_id: i32) -> Result<(), ApiError> {
+    let open = sqlx::query_scalar::<_, i64>(
+        "SELECT count(*) FROM rental WHERE customer_id = $1 AND return_date IS NULL",
+    )
+        .bind(customer_id)
+        .fetch_one(&mut **tx)
+        .timed("require_no_open_rentals")
+        .await?;
+    if open > 0 {
+        return Err(ApiError::conflict(format!("Customer still has {open} open rental(s)")));
+    }
+    Ok(())
+}
+
+/// Replaces the customer's names, email, store and address. Use the
+/// activate/deactivate endpoints to change whether they are active.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct UpdateCustomerForm {
+    store_id: i16,
+    first_name: String,
+    last_name: String,
+    email: Option<String>,
+    address: CreateAddress,
+}
+
+impl UpdateCustomerForm {
+    fn validate(&self) -> Result<(), ApiError> {
+        validate_customer(&self.first_name, &self.last_name, self.email.as_deref(), &self.address)
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated customer", body = GenericResponse<CustomerDetails, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "The customer belongs to, or would move to, another store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Customer not found", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 409, description = "Email is already in use, or the customer was erased", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 422, description = "A blank field, unknown store or a value the database rejects; `data` names the failed step", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[put("/{customer_id}")]
+pub async fn update_customer(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    path: web::Path<i32>,
+    data: web::Json<UpdateCustomerForm>,
+) -> Result<HttpResponse, ApiError> {
+    let customer_id = path.into_inner();
+    data.validate()?;
+
+    let mut tx = state.db.begin().timed("update_customer.begin").await.map_err(ApiError::step("transaction", None))?;
+    let customer = lock_customer(&mut tx, customer_id).await?;
+    staff.require_store(customer.store_id).map_err(|e| e.in_step("store", Some("store_id")))?;
+
+    if data.store_id != customer.store_id {
+        staff.require_store(data.store_id).map_err(|e| e.in_step("store", Some("store_id")))?;
+        require_store_exists(&mut tx, data.store_id).await?;
+    }
+    if let Some(email) = &data.email {
+        require_email_free(&mut tx, email, Some(customer_id)).await?;
+    }
+    let city_id = upsert_city(&mut tx, &data.address).await?;
+    let address_id = insert_address(&mut tx, &data.address, city_id).await?;
+
+    sqlx::query("
+    UPDATE customer
+    SET store_id = $1, first_name = $2, last_name = $3, email = $4, address_id = $5, last_update = now()
+    WHERE customer_id = $6
+    ")
+        .bind(data.store_id)
+        .bind(&data.first_name)
+        .bind(&data.last_name)
+        .bind(&data.email)
+        .bind(address_id)
+        .bind(customer_id)
+        .execute(&mut *tx)
+        .timed("update_customer.customer")
+        .await
+        .map_err(ApiError::step("customer", None))?;
+    delete_unused_address(&mut tx, customer.address_id).await?;
+
+    tx.commit().timed("update_customer.commit").await.map_err(ApiError::step("commit", None))?;
+
+    let customer = fetch_customer_details(&state.db, customer_id).await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(customer, "Successfully updated customer")))
+}
+
+/// Sets both `activebool` and the legacy integer `active` column.
+async fn set_active(
+    state: &AppState,
+    staff: &AuthenticatedStaff,
+    customer_id: i32,
+    active: bool,
+) -> Result<CustomerDetails, ApiError> {
+    let mut tx = state.db.begin().timed("set_active.begin").await?;
+    let customer = lock_customer(&mut tx, customer_id).await?;
+    staff.require_store(customer.store_id)?;
+    if !active {
+        require_no_open_rentals(&mut tx, customer_id).await?;
+    }
+    sqlx::query("UPDATE customer SET activebool = $1, active = $2, last_update = now() WHERE customer_id = $3")
+        .bind(active)
+        .bind(active as i32)
+        .bind(customer_id)
+        .execute(&mut *tx)
+        .timed("set_active.update")
+        .await?;
+    tx.commit().timed("set_active.commit").await?;
+    fetch_customer_details(&state.db, customer_id).await
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The deactivated customer", body = GenericResponse<CustomerDetails, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "The customer belongs to another store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Customer not found", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 409, description = "The customer has open rentals or was erased", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{customer_id}/deactivate")]
+pub async fn deactivate_customer(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let customer = set_active(&state, &staff, path.into_inner(), false).await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(customer, "Deactivated customer")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The reactivated customer", body = GenericResponse<CustomerDetails, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "The customer belongs to another store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Customer not found", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 409, description = "The customer was erased", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{customer_id}/activate")]
+pub async fn activate_customer(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let customer = set_active(&state, &staff, path.into_inner(), true).await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(customer, "Activated customer")))
+}
+
+/// Overwrites the customer's names, email and address and deactivates them.
+/// The row stays, so their rentals and payments still add up for accounting.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The anonymized customer", body = GenericResponse<CustomerDetails, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager of the customer's store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Customer not found", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 409, description = "The customer has open rentals or was already erased", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{customer_id}/erase")]
+pub async fn erase_customer(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    staff.require(Role::Manager)?;
+    let customer_id = path.into_inner();
+
+    let mut tx = state.db.begin().timed("erase_customer.begin").await?;
+    let customer = lock_customer(&mut tx, customer_id).await?;
+    staff.require_store(customer.store_id)?;
+    require_no_open_rentals(&mut tx, customer_id).await?;
+
+    // Only the city is kept, which is coarse enough to leave reports intact.
+    let address_id = sqlx::query_scalar::<_, i32>("
+    INSERT INTO address (address, district, city_id, phone)
+    SELECT '', '', city_id, '' FROM address WHERE address_id = $1
+    RETURNING address_id
+    ")
+        .bind(customer.address_id as i32)
+        .fetch_one(&mut *tx)
+        .timed("erase_customer.address")
+        .await?;
+    let address_id = smallint_id(address_id, "Address")?;
+    sqlx::query("
+    UPDATE customer
+    SET first_name = 'Erased', last_name = 'Customer', email = NULL, address_id = $1,
+        activebool = false, active = 0, erased_at = now(), last_update = now()
+    WHERE customer_id = $2
+    ")
+        .bind(address_id)
+        .bind(customer_id)
+        .execute(&mut *tx)
+        .timed("erase_customer.customer")
+        .await?;
+    delete_unused_address(&mut tx, customer.address_id).await?;
+    tx.commit().timed("erase_customer.commit").await?;
 
-    HttpResponse::Ok().json(GenericResponse::success(respond, "Successfully created customer"))
+    let customer = fetch_customer_details(&state.db, customer_id).await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(customer, "Erased customer's personal data")))
 }
 
-pub fn routes(cfg: &mut web::ServiceConfig) {
-    cfg
-        .service(get_total_customers_per_shop)
-        .service(get_customer_details)
-        .service(create_customer)
-        .service(get_customers_from_shop);
-}
\ No newline at end of file
+documented_routes!(routes, CustomersApi, [
+    get_total_customers_per_shop,
+    search::search_customers,
+    get_customer_details,
+    create_customer,
+    get_customers_from_shop,
+    update_customer,
+    deactivate_customer,
+    activate_customer,
+    erase_customer,
+    rentals::get_customer_rentals,
+    rentals::get_customer_loans,
+]);
\ No newline at end of file
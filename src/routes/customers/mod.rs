@@ -1,3 +1,5 @@
 pub mod customers;
+pub mod rentals;
+pub mod search;
 
-pub use customers::routes;
\ No newline at end of file
+pub use customers::{routes, CustomersApi};
\ No newline at end of file
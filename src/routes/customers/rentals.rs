@@ -0,0 +1,151 @@
+use crate::AppState;
+use crate::auth::ReportReader;
+use crate::metrics::Timed;
+use crate::models::{ApiError, GenericResponse, PageParams, PageQuery, StepDetails};
+
+use actix_web::{get, web, HttpResponse};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{self, FromRow, PgPool, Postgres, QueryBuilder};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CustomerRental {
+    rental_id: i32,
+    rental_date: chrono::NaiveDateTime,
+    return_date: Option<chrono::NaiveDateTime>,
+    inventory_id: i32,
+    film_id: i32,
+    title: String,
+    store_id: i16,
+    staff_id: i16,
+    staff_first_name: String,
+    staff_last_name: String,
+    /// Sum of the payments made against this rental so far.
+    amount_paid: Decimal,
+}
+
+async fn require_customer(db: &PgPool, customer_id: i32, query: &'static str) -> Result<(), ApiError> {
+    sqlx::query_scalar::<_, i32>("SELECT customer_id FROM customer WHERE customer_id = $1")
+        .bind(customer_id)
+        .fetch_optional(db)
+        .timed(query)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Customer not found"))?;
+    Ok(())
+}
+
+fn push_rentals_from(qb: &mut QueryBuilder<'_, Postgres>, customer_id: i32) {
+    qb.push("
+    FROM (
+        SELECT t1.rental_id, t1.rental_date, t1.return_date,
+            t1.inventory_id, t3.film_id, t3.title, t2.store_id,
+            t1.staff_id, t4.first_name as staff_first_name, t4.last_name as staff_last_name,
+            coalesce((SELECT sum(p.amount) FROM payment p WHERE p.rental_id = t1.rental_id), 0) as amount_paid
+        FROM rental t1
+        JOIN inventory t2
+            ON t2.inventory_id = t1.inventory_id
+        JOIN film t3
+            ON t3.film_id = t2.film_id
+        JOIN staff t4
+            ON t4.staff_id = t1.staff_id
+        WHERE t1.customer_id = ").push_bind(customer_id);
+    qb.push("
+    ) customer_rentals");
+}
+
+#[utoipa::path(
+    params(PageQuery),
+    responses(
+        (status = 200, description = "A page of the customer's rentals, newest first unless `sort` is given", body = GenericResponse<Vec<CustomerRental>, String>),
+        (status = 400, description = "Invalid paging or sort column", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "API key is missing the reports:read scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Customer not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/{customer_id}/rentals")]
+pub async fn get_customer_rentals(
+    state: web::Data<AppState>,
+    _reader: ReportReader,
+    path: web::Path<i32>,
+    page: PageParams,
+) -> Result<HttpResponse, ApiError> {
+    let customer_id = path.into_inner();
+    require_customer(&state.db, customer_id, "get_customer_rentals.customer").await?;
+
+    let mut count = QueryBuilder::new("SELECT count(*)");
+    push_rentals_from(&mut count, customer_id);
+    let total: i64 = count.build_query_scalar().fetch_one(&state.db).timed("get_customer_rentals.count").await?;
+
+    let mut query = QueryBuilder::new("SELECT *");
+    push_rentals_from(&mut query, customer_id);
+    match page.sort {
+        Some(_) => page.push_order_by(
+            &mut query,
+            &["rental_id", "rental_date", "return_date", "title", "store_id", "amount_paid"],
+            "rental_id",
+        )?,
+        None => {
+            query.push(" ORDER BY rental_date DESC, rental_id DESC");
+        }
+    }
+    page.push_limit(&mut query);
+    let rentals = query.build_query_as::<CustomerRental>()
+        .fetch_all(&state.db)
+        .timed("get_customer_rentals.page")
+        .await?;
+    Ok(HttpResponse::Ok().json(
+        GenericResponse::paginated(rentals, "Returned customer rentals", page.pagination(total))))
+}
+
+/// A copy the customer has not brought back yet, i.e. one Pagila's
+/// `inventory_held_by_customer` would attribute to them.
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Loan {
+    rental_id: i32,
+    rental_date: chrono::NaiveDateTime,
+    due_date: chrono::NaiveDateTime,
+    overdue: bool,
+    inventory_id: i32,
+    film_id: i32,
+    title: String,
+    store_id: i16,
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Copies the customer currently has out, earliest due first", body = GenericResponse<Vec<Loan>, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "API key is missing the reports:read scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Customer not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/{customer_id}/loans")]
+pub async fn get_customer_loans(
+    state: web::Data<AppState>,
+    _reader: ReportReader,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let customer_id = path.into_inner();
+    require_customer(&state.db, customer_id, "get_customer_loans.customer").await?;
+
+    let loans = sqlx::query_as::<_, Loan>("
+    SELECT t1.rental_id, t1.rental_date,
+        t1.rental_date + t3.rental_duration * interval '1 day' as due_date,
+        t1.rental_date + t3.rental_duration * interval '1 day' < now() as overdue,
+        t1.inventory_id, t3.film_id, t3.title, t2.store_id
+    FROM rental t1
+    JOIN inventory t2
+        ON t2.inventory_id = t1.inventory_id
+    JOIN film t3
+        ON t3.film_id = t2.film_id
+    WHERE t1.customer_id = $1
+    AND t1.return_date IS NULL
+    ORDER BY due_date, t1.rental_id")
+        .bind(customer_id)
+        .fetch_all(&state.db)
+        .timed("get_customer_loans")
+        .await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(loans, "Returned customer loans")))
+}
@@ -0,0 +1,141 @@
+use crate::AppState;
+use crate::auth::{AuthenticatedStaff, Role};
+use crate::metrics::Timed;
+use crate::models::{escape_like, ApiError, GenericResponse, PageParams, PageQuery, StepDetails};
+
+use actix_web::{get, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::{self, FromRow, Postgres, QueryBuilder};
+use utoipa::{IntoParams, ToSchema};
+
+/// `word_similarity` a name needs to count as a fuzzy match. pg_trgm defaults
+/// to 0.6, which misses a single typo in a short surname ("jonson").
+const NAME_SIMILARITY_THRESHOLD: &str = "0.3";
+
+/// Shorter terms have no trigrams to rank by and would match nearly everyone.
+const MIN_QUERY_CHARS: usize = 3;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CustomerSearchQuery {
+    /// Part of a name, email, phone number or postal code, at least three characters.
+    pub q: String,
+    /// Store to search; defaults to the caller's, and only managers may name another.
+    pub store_id: Option<i16>,
+    pub active: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CustomerSearchResult {
+    customer_id: i32,
+    store_id: i16,
+    first_name: String,
+    last_name: String,
+    email: Option<String>,
+    activebool: bool,
+    phone: String,
+    postal_code: Option<String>,
+    city: String,
+    /// Best `word_similarity` between `q` and any searched field, 1 for an exact word.
+    rank: f32,
+}
+
+/// Substring matches on every field, plus fuzzy matches on the full name so a
+/// misspelt surname still turns up. Erased customers have nothing left to find.
+fn push_search_from(qb: &mut QueryBuilder<'_, Postgres>, q: &str, store_id: i16, search: &CustomerSearchQuery) {
+    qb.push("
+    FROM (
+        SELECT t1.customer_id, t1.store_id, t1.first_name, t1.last_name, t1.email, t1.activebool,
+            t2.phone, t2.postal_code, t3.city,
+            greatest(
+                word_similarity(").push_bind(q.to_string()).push(", t1.first_name || ' ' || t1.last_name),
+                word_similarity(").push_bind(q.to_string()).push(", coalesce(t1.email, '')),
+                word_similarity(").push_bind(q.to_string()).push(", t2.phone),
+                word_similarity(").push_bind(q.to_string()).push(", coalesce(t2.postal_code, ''))
+            ) as rank
+        FROM customer t1
+        JOIN address t2
+            ON t1.address_id = t2.address_id
+        JOIN city t3
+            ON t2.city_id = t3.city_id
+        WHERE t1.erased_at IS NULL
+        AND (");
+    let pattern = format!("%{}%", escape_like(q));
+    qb.push("t1.first_name || ' ' || t1.last_name ILIKE ").push_bind(pattern.clone()).push(" ESCAPE '\\'");
+    qb.push(" OR t1.email ILIKE ").push_bind(pattern.clone()).push(" ESCAPE '\\'");
+    qb.push(" OR t2.phone ILIKE ").push_bind(pattern.clone()).push(" ESCAPE '\\'");
+    qb.push(" OR t2.postal_code ILIKE ").push_bind(pattern).push(" ESCAPE '\\'");
+    qb.push(" OR ").push_bind(q.to_string()).push(" <% (t1.first_name || ' ' || t1.last_name))");
+    qb.push(" AND t1.store_id = ").push_bind(store_id);
+    if let Some(active) = search.active {
+        qb.push(" AND t1.activebool = ").push_bind(active);
+    }
+    qb.push("
+    ) matches");
+}
+
+/// Counter lookup by name, email, phone or postal code, case-insensitive and
+/// tolerant of typos in names. Ranked with `pg_trgm`'s `word_similarity`.
+/// Staff see their own store's customers unless a manager names another.
+#[utoipa::path(
+    params(CustomerSearchQuery, PageQuery),
+    responses(
+        (status = 200, description = "A page of matches, best first unless `sort` is given", body = GenericResponse<Vec<CustomerSearchResult>, String>),
+        (status = 400, description = "Search term shorter than three characters, or invalid paging or sort column", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "A clerk asked for another store's customers", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/search")]
+pub async fn search_customers(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    page: PageParams,
+    search: web::Query<CustomerSearchQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let q = search.q.trim();
+    if q.chars().count() < MIN_QUERY_CHARS {
+        return Err(ApiError::bad_request(format!("q must be at least {MIN_QUERY_CHARS} characters")));
+    }
+    let store_id = match search.store_id {
+        Some(store_id) if store_id != staff.store_id => {
+            staff.require(Role::Manager)?;
+            store_id
+        }
+        _ => staff.store_id,
+    };
+
+    // `<%` reads its threshold from the session; setting it locally keeps the
+    // fuzzy match indexable without leaking the setting to other pool users.
+    let mut tx = state.db.begin().timed("search_customers.begin").await?;
+    sqlx::query("SELECT set_config('pg_trgm.word_similarity_threshold', $1, true)")
+        .bind(NAME_SIMILARITY_THRESHOLD)
+        .execute(&mut *tx)
+        .timed("search_customers.threshold")
+        .await?;
+
+    let mut count = QueryBuilder::new("SELECT count(*)");
+    push_search_from(&mut count, q, store_id, &search);
+    let total: i64 = count.build_query_scalar().fetch_one(&mut *tx).timed("search_customers.count").await?;
+
+    let mut query = QueryBuilder::new("SELECT *");
+    push_search_from(&mut query, q, store_id, &search);
+    match page.sort {
+        Some(_) => page.push_order_by(
+            &mut query,
+            &["customer_id", "first_name", "last_name", "email", "store_id", "rank"],
+            "customer_id",
+        )?,
+        None => {
+            query.push(" ORDER BY rank DESC, customer_id");
+        }
+    }
+    page.push_limit(&mut query);
+    let customers = query.build_query_as::<CustomerSearchResult>()
+        .fetch_all(&mut *tx)
+        .timed("search_customers.page")
+        .await?;
+    tx.commit().timed("search_customers.commit").await?;
+    Ok(HttpResponse::Ok().json(
+        GenericResponse::paginated(customers, "Returned matching customers", page.pagination(total))))
+}
@@ -0,0 +1,39 @@
+use actix_web::http::header::ContentType;
+use actix_web::{get, web, HttpResponse};
+use utoipa::OpenApi;
+
+use super::ApiDoc;
+
+/// Swagger UI loaded from a CDN, pointed at the document below.
+const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="utf-8">
+    <title>Film rental API</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+<div id="swagger-ui"></div>
+<script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+<script>
+    window.onload = () => {
+        window.ui = SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+    };
+</script>
+</body>
+</html>
+"##;
+
+#[get("/openapi.json")]
+async fn openapi_json() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+#[get("/docs")]
+async fn swagger_ui() -> HttpResponse {
+    HttpResponse::Ok().content_type(ContentType::html()).body(SWAGGER_UI)
+}
+
+pub fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(openapi_json).service(swagger_ui);
+}
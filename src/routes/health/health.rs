@@ -0,0 +1,131 @@
+use std::time::{Duration, Instant};
+
+use actix_web::rt::time::timeout;
+use actix_web::{get, web, HttpResponse};
+use serde::Serialize;
+use sqlx::Connection;
+use utoipa::ToSchema;
+
+use crate::auth::{AuthenticatedStaff, Role};
+use crate::metrics::{self, Timed};
+use crate::models::{ApiError, GenericResponse, StepDetails};
+use crate::AppState;
+
+/// How long `/ready` waits for a connection and a round trip before reporting
+/// the service as not ready. Well below the usual probe timeout of a few seconds.
+const READY_DEADLINE: Duration = Duration::from_secs(2);
+
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "The process is up", body = GenericResponse<utoipa::TupleUnit, String>),
+    ),
+)]
+#[get("/health")]
+pub async fn health() -> HttpResponse {
+    HttpResponse::Ok().json(GenericResponse::success((), "Up"))
+}
+
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "The database answered within the deadline", body = GenericResponse<utoipa::TupleUnit, String>),
+        (status = 503, description = "No connection or no answer within the deadline", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/ready")]
+pub async fn ready(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let check = async {
+        let mut conn = state.db.acquire().await?;
+        conn.ping().await
+    };
+    match timeout(READY_DEADLINE, check).await {
+        Ok(Ok(())) => Ok(HttpResponse::Ok().json(GenericResponse::success((), "Ready"))),
+        Ok(Err(e)) => Err(ApiError::unavailable(format!("Database unavailable: {e}"))),
+        Err(_) => Err(ApiError::unavailable(format!(
+            "Database did not answer within {} ms",
+            READY_DEADLINE.as_millis()
+        ))),
+    }
+}
+
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "Prometheus metrics in the text exposition format", body = String, content_type = "text/plain"),
+    ),
+)]
+#[get("/metrics")]
+pub async fn prometheus_metrics(state: web::Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics::render(&state.db))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PoolDiagnostics {
+    /// Open connections, idle or checked out.
+    size: u32,
+    idle: usize,
+    min_connections: u32,
+    max_connections: u32,
+    acquire_timeout_ms: u128,
+    idle_timeout_secs: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Diagnostics {
+    /// Pool state before this request checked out its connection.
+    pool: PoolDiagnostics,
+    /// How long this request waited for a connection.
+    acquire_wait_ms: f64,
+    /// Round trip of `SELECT version()`.
+    query_ms: f64,
+    server_version: String,
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Connection pool and database server state", body = GenericResponse<Diagnostics, String>),
+        (status = 401, description = "Not logged in", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 503, description = "No connection within the acquire timeout", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/diagnostics")]
+pub async fn diagnostics(state: web::Data<AppState>, staff: AuthenticatedStaff) -> Result<HttpResponse, ApiError> {
+    staff.require(Role::Manager)?;
+    let options = state.db.options();
+    let pool = PoolDiagnostics {
+        size: state.db.size(),
+        idle: state.db.num_idle(),
+        min_connections: options.get_min_connections(),
+        max_connections: options.get_max_connections(),
+        acquire_timeout_ms: options.get_acquire_timeout().as_millis(),
+        idle_timeout_secs: options.get_idle_timeout().map(|idle| idle.as_secs()),
+    };
+
+    let started = Instant::now();
+    let mut conn = state
+        .db
+        .acquire()
+        .await
+        .map_err(|e| ApiError::unavailable(format!("Database unavailable: {e}")))?;
+    let acquire_wait = started.elapsed();
+
+    let started = Instant::now();
+    let server_version: String = sqlx::query_scalar("SELECT version()").fetch_one(&mut *conn).timed("diagnostics.version").await?;
+    let query = started.elapsed();
+
+    let diagnostics = Diagnostics {
+        pool,
+        acquire_wait_ms: acquire_wait.as_secs_f64() * 1000.0,
+        query_ms: query.as_secs_f64() * 1000.0,
+        server_version,
+    };
+    Ok(HttpResponse::Ok().json(GenericResponse::success(diagnostics, "Returned diagnostics")))
+}
+
+// Probes and metrics sit outside `/api` so orchestrators and scrapers skip authentication.
+documented_routes!(probe_routes, ProbesApi, [health, ready, prometheus_metrics]);
+documented_routes!(routes, DiagnosticsApi, [diagnostics]);
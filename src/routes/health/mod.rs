@@ -0,0 +1,3 @@
+pub mod health;
+
+pub use health::{probe_routes, routes, DiagnosticsApi, ProbesApi};
\ No newline at end of file
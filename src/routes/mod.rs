@@ -1,19 +1,120 @@
+use actix_web::middleware::from_fn;
 use actix_web::web;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::auth::{api_key::API_KEY_HEADER, require_write_access, ApiScope, Role, WriteAccess};
+
+/// Declares a module's handlers once: `$routes` registers them in order and `$doc`
+/// lists the same handlers for the OpenAPI document, so every served route is documented.
+macro_rules! documented_routes {
+    ($routes:ident, $doc:ident, [$($($handler:ident)::+),* $(,)?]) => {
+        #[derive(utoipa::OpenApi)]
+        #[openapi(paths($($($handler)::+),*))]
+        pub struct $doc;
+
+        pub fn $routes(cfg: &mut actix_web::web::ServiceConfig) {
+            cfg$(.service($($handler)::+))*;
+        }
+    };
+}
 
 pub mod actors;
+pub mod api_keys;
 pub mod cities;
 pub mod counter;
 pub mod movies;
 pub mod customers;
+pub mod docs;
+pub mod health;
+pub mod payments;
+pub mod rentals;
+pub mod staff;
 pub mod stores;
 
 pub use counter::counter_routes;
+pub use health::probe_routes;
+
+/// The catalogue (actors, cities, movies) is maintained by store managers and
+/// by API clients with the `catalogue:write` scope.
+const CATALOGUE: WriteAccess = WriteAccess { role: Role::Manager, api_scope: Some(ApiScope::CatalogueWrite) };
+/// Front-desk work: customers, rentals and payments.
+const FRONT_DESK: WriteAccess = WriteAccess { role: Role::Clerk, api_scope: None };
+const STORE_MANAGEMENT: WriteAccess = WriteAccess { role: Role::Manager, api_scope: None };
+
+/// The document served at `/api/openapi.json`. Each module is nested under the
+/// scope it is mounted on and tagged with its module name.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Film rental API", description = "Pagila film rental stores: catalogue, customers, rentals and payments."),
+    nest(
+        (path = "/api/actors", api = actors::ActorsApi),
+        (path = "/api/api-keys", api = api_keys::ApiKeysApi),
+        (path = "/api/cities", api = cities::CitiesApi),
+        (path = "/counter", api = counter::CounterApi),
+        (path = "/api/customers", api = customers::CustomersApi),
+        (path = "/api/customers", api = payments::CustomerPaymentsApi),
+        (path = "/api", api = health::DiagnosticsApi),
+        (path = "/api/movies", api = movies::MoviesApi),
+        (path = "/api/payments", api = payments::PaymentsApi),
+        (path = "/api/rentals", api = rentals::RentalsApi),
+        (path = "/api/staff", api = staff::StaffApi),
+        (path = "/api/stores", api = stores::StoresApi),
+    ),
+    modifiers(&DocAddon),
+    security((), ("bearer" = []), ("api_key" = [])),
+)]
+pub struct ApiDoc;
+
+/// Reads need no credentials, except reports, which take a staff JWT or an API
+/// key with the `reports:read` scope. Writes take a staff JWT from
+/// `/api/staff/login` or, for the catalogue, an API key with `catalogue:write`.
+/// The probes live outside `/api`, so they are merged rather than nested.
+struct DocAddon;
+
+impl Modify for DocAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        // Cargo.toml has no license, which utoipa would otherwise render as an empty one.
+        openapi.info.license = None;
+        openapi.merge(health::ProbesApi::openapi());
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+        components.add_security_scheme("api_key", SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))));
+    }
+}
 
+/// Reads are open to every caller apart from reports, whose handlers take a
+/// `ReportReader`. Writes need the `WriteAccess` guarding their scope. `staff`
+/// and `api-keys` check roles per handler because login has to stay open.
 pub fn api_routes(cfg: &mut web::ServiceConfig) {
     cfg
-        .service(web::scope("actors").configure(actors::routes))
-        .service(web::scope("cities").configure(cities::routes))
-        .service(web::scope("customers").configure(customers::routes))
-        .service(web::scope("movies").configure(movies::routes))
-        .service(web::scope("stores").configure(stores::routes));
+        .service(web::scope("actors")
+            .wrap(from_fn(|req, next| require_write_access(req, next, CATALOGUE)))
+            .configure(actors::routes))
+        .service(web::scope("api-keys").configure(api_keys::routes))
+        .service(web::scope("cities")
+            .wrap(from_fn(|req, next| require_write_access(req, next, CATALOGUE)))
+            .configure(cities::routes))
+        .service(web::scope("customers")
+            .wrap(from_fn(|req, next| require_write_access(req, next, FRONT_DESK)))
+            .configure(customers::routes)
+            .configure(payments::customer_routes))
+        .service(web::scope("movies")
+            .wrap(from_fn(|req, next| require_write_access(req, next, CATALOGUE)))
+            .configure(movies::routes))
+        .service(web::scope("payments")
+            .wrap(from_fn(|req, next| require_write_access(req, next, FRONT_DESK)))
+            .configure(payments::routes))
+        .service(web::scope("rentals")
+            .wrap(from_fn(|req, next| require_write_access(req, next, FRONT_DESK)))
+            .configure(rentals::routes))
+        .service(web::scope("staff").configure(staff::routes))
+        .service(web::scope("stores")
+            .wrap(from_fn(|req, next| require_write_access(req, next, STORE_MANAGEMENT)))
+            .configure(stores::routes))
+        .configure(docs::routes)
+        .configure(health::routes);
 }
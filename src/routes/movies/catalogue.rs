@@ -0,0 +1,447 @@
+use crate::AppState;
+use crate::metrics::Timed;
+use crate::models::{ApiError, GenericResponse, StepDetails};
+
+use actix_web::{delete, post, put, web, HttpResponse};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{self, Postgres, Transaction};
+use utoipa::ToSchema;
+
+use super::movies::{fetch_movie_details, MovieDetailsResponse};
+
+/// Values of the `mpaa_rating` enum.
+const MPAA_RATINGS: &[&str] = &["G", "PG", "PG-13", "R", "NC-17"];
+
+/// Values allowed in `film.special_features`.
+const SPECIAL_FEATURES: &[&str] = &["Trailers", "Commentaries", "Deleted Scenes", "Behind the Scenes"];
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct MovieForm {
+    title: String,
+    description: Option<String>,
+    release_year: Option<i32>,
+    language_id: i16,
+    original_language_id: Option<i16>,
+    rental_duration: i16,
+    rental_rate: Decimal,
+    length: Option<i16>,
+    replacement_cost: Decimal,
+    rating: Option<String>,
+    #[serde(default)]
+    special_features: Vec<String>,
+    /// Replaces the film's cast when present.
+    actor_ids: Option<Vec<i32>>,
+    /// Replaces the film's categories when present.
+    category_ids: Option<Vec<i32>>,
+}
+
+fn invalid(field: &'static str, message: impl Into<String>) -> ApiError {
+    ApiError::unprocessable(message).in_step("validation", Some(field))
+}
+
+impl MovieForm {
+    fn validate(&self) -> Result<(), ApiError> {
+        if self.title.trim().is_empty() {
+            return Err(invalid("title", "title must not be empty"));
+        }
+        if let Some(year) = self.release_year {
+            if !(1901..=2155).contains(&year) {
+                return Err(invalid("release_year", "release_year must be between 1901 and 2155"));
+            }
+        }
+        if self.rental_duration < 1 {
+            return Err(invalid("rental_duration", "rental_duration must be at least 1 day"));
+        }
+        if self.rental_rate < Decimal::ZERO {
+            return Err(invalid("rental_rate", "rental_rate must not be negative"));
+        }
+        if self.replacement_cost < Decimal::ZERO {
+            return Err(invalid("replacement_cost", "replacement_cost must not be negative"));
+        }
+        if let Some(length) = self.length {
+            if length < 1 {
+                return Err(invalid("length", "length must be positive"));
+            }
+        }
+        if let Some(rating) = &self.rating {
+            if !MPAA_RATINGS.contains(&rating.as_str()) {
+                return Err(invalid("rating", format!("rating must be one of: {}", MPAA_RATINGS.join(", "))));
+            }
+        }
+        for (i, feature) in self.special_features.iter().enumerate() {
+            if !SPECIAL_FEATURES.contains(&feature.as_str()) {
+                return Err(invalid(
+                    "special_features",
+                    format!("special_features must only contain: {}", SPECIAL_FEATURES.join(", ")),
+                ));
+            }
+            if self.special_features[..i].contains(feature) {
+                return Err(invalid("special_features", format!("{feature} is listed twice")));
+            }
+        }
+        Ok(())
+    }
+
+    async fn check_references(&self, tx: &mut Transaction<'_, Postgres>) -> Result<(), ApiError> {
+        let languages: Vec<i16> = [Some(self.language_id), self.original_language_id]
+            .into_iter()
+            .flatten()
+            .collect();
+        let missing = missing_ids(
+            tx,
+            "language",
+            "language_id",
+            languages.iter().map(|id| *id as i32).collect(),
+            "check_references.languages",
+        )
+        .await?;
+        if missing.contains(&(self.language_id as i32)) {
+            return Err(invalid("language_id", "Unknown language"));
+        }
+        if !missing.is_empty() {
+            return Err(invalid("original_language_id", "Unknown language"));
+        }
+        if let Some(actor_ids) = &self.actor_ids {
+            let missing = missing_ids(tx, "actor", "actor_id", actor_ids.clone(), "check_references.actors").await?;
+            if !missing.is_empty() {
+                return Err(invalid("actor_ids", format!("Unknown actors: {missing:?}")));
+            }
+        }
+        if let Some(category_ids) = &self.category_ids {
+            let missing = missing_ids(tx, "category", "category_id", category_ids.clone(), "check_references.categories").await?;
+            if !missing.is_empty() {
+                return Err(invalid("category_ids", format!("Unknown categories: {missing:?}")));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the ids in `ids` that have no row in `table`, timed under `query`.
+/// Only called with fixed table names.
+async fn missing_ids(
+    tx: &mut Transaction<'_, Postgres>,
+    table: &str,
+    column: &str,
+    ids: Vec<i32>,
+    query: &'static str,
+) -> Result<Vec<i32>, ApiError> {
+    let missing = sqlx::query_scalar::<_, i32>(&format!("
+    SELECT id FROM unnest($1::int[]) id
+    WHERE NOT EXISTS (SELECT 1 FROM {table} WHERE {column} = id)
+    "))
+        .bind(ids)
+        .fetch_all(&mut **tx)
+        .timed(query)
+        .await?;
+    Ok(missing)
+}
+
+async fn replace_assignments(
+    tx: &mut Transaction<'_, Postgres>,
+    film_id: i32,
+    form: &MovieForm,
+) -> Result<(), ApiError> {
+    if let Some(actor_ids) = &form.actor_ids {
+        sqlx::query("DELETE FROM film_actor WHERE film_id = $1")
+            .bind(film_id)
+            .execute(&mut **tx)
+            .timed("replace_assignments.delete_actors")
+            .await?;
+        sqlx::query("
+        INSERT INTO film_actor (actor_id, film_id)
+        SELECT DISTINCT unnest($1::int[]), $2
+        ")
+            .bind(actor_ids)
+            .bind(film_id)
+            .execute(&mut **tx)
+            .timed("replace_assignments.insert_actors")
+            .await?;
+    }
+    if let Some(category_ids) = &form.category_ids {
+        sqlx::query("DELETE FROM film_category WHERE film_id = $1")
+            .bind(film_id)
+            .execute(&mut **tx)
+            .timed("replace_assignments.delete_categories")
+            .await?;
+        sqlx::query("
+        INSERT INTO film_category (category_id, film_id)
+        SELECT DISTINCT unnest($1::int[]), $2
+        ")
+            .bind(category_ids)
+            .bind(film_id)
+            .execute(&mut **tx)
+            .timed("replace_assignments.insert_categories")
+            .await?;
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The new movie", body = GenericResponse<MovieDetailsResponse, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager or missing the catalogue:write scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 422, description = "Invalid field or unknown reference; `data` names the field", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("")]
+pub async fn create_movie(state: web::Data<AppState>, form: web::Json<MovieForm>) -> Result<HttpResponse, ApiError> {
+    form.validate()?;
+    let mut tx = state.db.begin().timed("create_movie.begin").await?;
+    form.check_references(&mut tx).await?;
+
+    let film_id = sqlx::query_scalar::<_, i32>("
+    INSERT INTO film (title, description, release_year, language_id, original_language_id,
+        rental_duration, rental_rate, length, replacement_cost, rating, special_features)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10::mpaa_rating, $11)
+    RETURNING film_id
+    ")
+        .bind(&form.title)
+        .bind(&form.description)
+        .bind(form.release_year)
+        .bind(form.language_id)
+        .bind(form.original_language_id)
+        .bind(form.rental_duration)
+        .bind(form.rental_rate)
+        .bind(form.length)
+        .bind(form.replacement_cost)
+        .bind(&form.rating)
+        .bind(&form.special_features)
+        .fetch_one(&mut *tx)
+        .timed("create_movie.insert")
+        .await?;
+    replace_assignments(&mut tx, film_id, &form).await?;
+    tx.commit().timed("create_movie.commit").await?;
+
+    let details = fetch_movie_details(&state.db, film_id).await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(details, "Successfully added new movie")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated movie", body = GenericResponse<MovieDetailsResponse, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager or missing the catalogue:write scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Movie not found", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 422, description = "Invalid field or unknown reference; `data` names the field", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[put("/{film_id:\\d+}")]
+pub async fn update_movie(
+    state: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<MovieForm>,
+) -> Result<HttpResponse, ApiError> {
+    let film_id = path.into_inner();
+    form.validate()?;
+    let mut tx = state.db.begin().timed("update_movie.begin").await?;
+    form.check_references(&mut tx).await?;
+
+    sqlx::query_scalar::<_, i32>("
+    UPDATE film
+    SET title = $1, description = $2, release_year = $3, language_id = $4,
+        original_language_id = $5, rental_duration = $6, rental_rate = $7, length = $8,
+        replacement_cost = $9, rating = $10::mpaa_rating, special_features = $11
+    WHERE film_id = $12
+    RETURNING film_id
+    ")
+        .bind(&form.title)
+        .bind(&form.description)
+        .bind(form.release_year)
+        .bind(form.language_id)
+        .bind(form.original_language_id)
+        .bind(form.rental_duration)
+        .bind(form.rental_rate)
+        .bind(form.length)
+        .bind(form.replacement_cost)
+        .bind(&form.rating)
+        .bind(&form.special_features)
+        .bind(film_id)
+        .fetch_optional(&mut *tx)
+        .timed("update_movie.update")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Movie not found"))?;
+    replace_assignments(&mut tx, film_id, &form).await?;
+    tx.commit().timed("update_movie.commit").await?;
+
+    let details = fetch_movie_details(&state.db, film_id).await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(details, "Successfully updated movie")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Movie deleted", body = GenericResponse<utoipa::TupleUnit, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager or missing the catalogue:write scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Movie not found", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 409, description = "Copies of the movie are still in inventory", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[delete("/{film_id:\\d+}")]
+pub async fn delete_movie(state: web::Data<AppState>, path: web::Path<i32>) -> Result<HttpResponse, ApiError> {
+    let film_id = path.into_inner();
+    let mut tx = state.db.begin().timed("delete_movie.begin").await?;
+
+    lock_film(&mut tx, film_id).await?;
+    let copies = sqlx::query_scalar::<_, i64>("SELECT count(*) FROM inventory WHERE film_id = $1")
+        .bind(film_id)
+        .fetch_one(&mut *tx)
+        .timed("delete_movie.copies")
+        .await?;
+    if copies > 0 {
+        return Err(ApiError::conflict(format!("Movie still has {copies} copies in inventory")));
+    }
+
+    sqlx::query("DELETE FROM film_actor WHERE film_id = $1")
+        .bind(film_id)
+        .execute(&mut *tx)
+        .timed("delete_movie.film_actor")
+        .await?;
+    sqlx::query("DELETE FROM film_category WHERE film_id = $1")
+        .bind(film_id)
+        .execute(&mut *tx)
+        .timed("delete_movie.film_category")
+        .await?;
+    sqlx::query("DELETE FROM film WHERE film_id = $1")
+        .bind(film_id)
+        .execute(&mut *tx)
+        .timed("delete_movie.film")
+        .await?;
+    tx.commit().timed("delete_movie.commit").await?;
+
+    Ok(HttpResponse::Ok().json(GenericResponse::success((), format!("Deleted movie {film_id}"))))
+}
+
+async fn lock_film(tx: &mut Transaction<'_, Postgres>, film_id: i32) -> Result<(), ApiError> {
+    sqlx::query("SELECT film_id FROM film WHERE film_id = $1 FOR UPDATE")
+        .bind(film_id)
+        .fetch_optional(&mut **tx)
+        .timed("lock_film")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Movie not found"))?;
+    Ok(())
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The movie with its new cast", body = GenericResponse<MovieDetailsResponse, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager or missing the catalogue:write scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Movie or actor not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{film_id:\\d+}/actors/{actor_id}")]
+pub async fn attach_actor(state: web::Data<AppState>, path: web::Path<(i32, i32)>) -> Result<HttpResponse, ApiError> {
+    let (film_id, actor_id) = path.into_inner();
+    let mut tx = state.db.begin().timed("attach_actor.begin").await?;
+    lock_film(&mut tx, film_id).await?;
+    if !missing_ids(&mut tx, "actor", "actor_id", vec![actor_id], "attach_actor.actor").await?.is_empty() {
+        return Err(ApiError::not_found("Actor not found"));
+    }
+    sqlx::query("
+    INSERT INTO film_actor (actor_id, film_id)
+    VALUES ($1, $2)
+    ON CONFLICT DO NOTHING
+    ")
+        .bind(actor_id)
+        .bind(film_id)
+        .execute(&mut *tx)
+        .timed("attach_actor.insert")
+        .await?;
+    tx.commit().timed("attach_actor.commit").await?;
+
+    let details = fetch_movie_details(&state.db, film_id).await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(details, "Actor assigned to movie")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The movie with its new cast", body = GenericResponse<MovieDetailsResponse, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager or missing the catalogue:write scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Movie not found or actor not assigned to it", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[delete("/{film_id:\\d+}/actors/{actor_id}")]
+pub async fn detach_actor(state: web::Data<AppState>, path: web::Path<(i32, i32)>) -> Result<HttpResponse, ApiError> {
+    let (film_id, actor_id) = path.into_inner();
+    let mut tx = state.db.begin().timed("detach_actor.begin").await?;
+    lock_film(&mut tx, film_id).await?;
+    let deleted = sqlx::query("DELETE FROM film_actor WHERE film_id = $1 AND actor_id = $2")
+        .bind(film_id)
+        .bind(actor_id)
+        .execute(&mut *tx)
+        .timed("detach_actor.delete")
+        .await?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(ApiError::not_found("Actor is not assigned to this movie"));
+    }
+    tx.commit().timed("detach_actor.commit").await?;
+
+    let details = fetch_movie_details(&state.db, film_id).await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(details, "Actor removed from movie")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The movie with its new categories", body = GenericResponse<MovieDetailsResponse, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager or missing the catalogue:write scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Movie or category not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{film_id:\\d+}/categories/{category_id}")]
+pub async fn attach_category(state: web::Data<AppState>, path: web::Path<(i32, i32)>) -> Result<HttpResponse, ApiError> {
+    let (film_id, category_id) = path.into_inner();
+    let mut tx = state.db.begin().timed("attach_category.begin").await?;
+    lock_film(&mut tx, film_id).await?;
+    if !missing_ids(&mut tx, "category", "category_id", vec![category_id], "attach_category.category").await?.is_empty() {
+        return Err(ApiError::not_found("Category not found"));
+    }
+    sqlx::query("
+    INSERT INTO film_category (category_id, film_id)
+    VALUES ($1, $2)
+    ON CONFLICT DO NOTHING
+    ")
+        .bind(category_id)
+        .bind(film_id)
+        .execute(&mut *tx)
+        .timed("attach_category.insert")
+        .await?;
+    tx.commit().timed("attach_category.commit").await?;
+
+    let details = fetch_movie_details(&state.db, film_id).await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(details, "Category assigned to movie")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The movie with its new categories", body = GenericResponse<MovieDetailsResponse, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager or missing the catalogue:write scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Movie not found or category not assigned to it", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[delete("/{film_id:\\d+}/categories/{category_id}")]
+pub async fn detach_category(state: web::Data<AppState>, path: web::Path<(i32, i32)>) -> Result<HttpResponse, ApiError> {
+    let (film_id, category_id) = path.into_inner();
+    let mut tx = state.db.begin().timed("detach_category.begin").await?;
+    lock_film(&mut tx, film_id).await?;
+    let deleted = sqlx::query("DELETE FROM film_category WHERE film_id = $1 AND category_id = $2")
+        .bind(film_id)
+        .bind(category_id)
+        .execute(&mut *tx)
+        .timed("detach_category.delete")
+        .await?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(ApiError::not_found("Category is not assigned to this movie"));
+    }
+    tx.commit().timed("detach_category.commit").await?;
+
+    let details = fetch_movie_details(&state.db, film_id).await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(details, "Category removed from movie")))
+}
@@ -1,3 +1,5 @@
+pub mod catalogue;
 pub mod movies;
+pub mod search;
 
-pub use movies::routes;
\ No newline at end of file
+pub use movies::{routes, MoviesApi};
\ No newline at end of file
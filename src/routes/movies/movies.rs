@@ -1,48 +1,101 @@
 use crate::AppState;
-use crate::models::GenericResponse;
+use crate::metrics::Timed;
+use crate::models::{escape_like, ApiError, GenericResponse, PageParams, PageQuery, StepDetails};
+use super::{catalogue, search};
 
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{get, web, HttpResponse};
 use serde::{Deserialize, Serialize};
-use sqlx::{self, FromRow};
+use sqlx::{self, FromRow, PgPool, Postgres, QueryBuilder};
 use rust_decimal;
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Serialize, Deserialize, FromRow)]
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Movies {
     film_id: i32,
     title: String,
-    description: String,
-    release_year: i32,
+    description: Option<String>,
+    release_year: Option<i32>,
     language_id: i16,
     replacement_cost: rust_decimal::Decimal,
-    rating: String,
+    rating: Option<String>,
 }
 
+/// `title` is a case-insensitive substring.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MovieFilter {
+    pub title: Option<String>,
+    pub rating: Option<String>,
+    pub language_id: Option<i16>,
+    pub release_year: Option<i32>,
+}
+
+fn push_movie_filters(qb: &mut QueryBuilder<'_, Postgres>, filter: &MovieFilter) {
+    qb.push(" WHERE true");
+    if let Some(title) = &filter.title {
+        qb.push(" AND title ILIKE ").push_bind(format!("%{}%", escape_like(title))).push(" ESCAPE '\\'");
+    }
+    if let Some(rating) = &filter.rating {
+        qb.push(" AND rating::text = ").push_bind(rating.clone());
+    }
+    if let Some(language_id) = filter.language_id {
+        qb.push(" AND language_id = ").push_bind(language_id);
+    }
+    if let Some(release_year) = filter.release_year {
+        qb.push(" AND release_year = ").push_bind(release_year);
+    }
+}
+
+#[utoipa::path(
+    params(MovieFilter, PageQuery),
+    responses(
+        (status = 200, description = "A page of movies", body = GenericResponse<Vec<Movies>, String>),
+        (status = 400, description = "Invalid paging, filter or sort column", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
 #[get("")]
-pub async fn get_all_movies(state: web::Data<AppState>) -> impl Responder {
-    match sqlx::query_as::<_, Movies>("
-    SELECT * FROM film
-    ")
+pub async fn get_all_movies(
+    state: web::Data<AppState>,
+    page: PageParams,
+    filter: web::Query<MovieFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let mut count = QueryBuilder::new("SELECT count(*) FROM film");
+    push_movie_filters(&mut count, &filter);
+    let total: i64 = count.build_query_scalar().fetch_one(&state.db).timed("get_all_movies.count").await?;
+
+    let mut query = QueryBuilder::new("
+    SELECT film_id, title, description, release_year::int as release_year, language_id,
+        replacement_cost, rating::text as rating
+    FROM film");
+    push_movie_filters(&mut query, &filter);
+    page.push_order_by(
+        &mut query,
+        &["film_id", "title", "release_year", "replacement_cost", "rating"],
+        "film_id",
+    )?;
+    page.push_limit(&mut query);
+    let movies = query.build_query_as::<Movies>()
         .fetch_all(&state.db)
-        .await
-    {
-        Ok(movies) => HttpResponse::Ok().json(
-            GenericResponse::success(movies, "Returned all movies")),
-        Err(e) => {
-            println!("{e}");
-            HttpResponse::NotFound().json(GenericResponse::error((), "Didn't find any movies"))
-        }
-    }
+        .timed("get_all_movies.page")
+        .await?;
+    Ok(HttpResponse::Ok().json(
+        GenericResponse::paginated(movies, "Returned all movies", page.pagination(total))))
 }
 
-#[derive(FromRow, Deserialize, Serialize)]
+#[derive(FromRow, Deserialize, Serialize, ToSchema)]
 pub struct TotalMoviesPerCategory {
     category_name: String,
     count: i64,
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Movie count per category", body = GenericResponse<Vec<TotalMoviesPerCategory>, String>),
+    ),
+)]
 #[get("/total_by_category")]
-pub async fn get_total_movies_per_category(state: web::Data<AppState>) -> impl Responder {
-    match sqlx::query_as::<_, TotalMoviesPerCategory>("\
+pub async fn get_total_movies_per_category(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let movies = sqlx::query_as::<_, TotalMoviesPerCategory>("\
     SELECT t1.name as category_name, count(*) as count
     FROM category t1
     JOIN film_category t2
@@ -51,25 +104,26 @@ pub async fn get_total_movies_per_category(state: web::Data<AppState>) -> impl R
     ORDER BY count DESC;
     ")
         .fetch_all(&state.db)
-        .await {
-        Ok(movies) => HttpResponse::Ok()
-            .json(GenericResponse::success(movies, "Returned total movies per category")),
-        Err(e) => {
-            println!("{e}");
-            HttpResponse::NotFound().json(GenericResponse::error((), "Movies not found"))
-        }
-    }
+        .timed("get_total_movies_per_category")
+        .await?;
+    Ok(HttpResponse::Ok()
+        .json(GenericResponse::success(movies, "Returned total movies per category")))
 }
 
-#[derive(FromRow, Serialize, Deserialize)]
+#[derive(FromRow, Serialize, Deserialize, ToSchema)]
 pub struct TopMovies {
     title: String,
     count: i64,
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The three most rented titles", body = GenericResponse<Vec<TopMovies>, String>),
+    ),
+)]
 #[get("/top_3_rented")]
-pub async fn top_3_rented(state: web::Data<AppState>) -> impl Responder {
-    match sqlx::query_as::<_, TopMovies>("
+pub async fn top_3_rented(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let top = sqlx::query_as::<_, TopMovies>("
     SELECT t3.title, count(*)
     FROM rental t1
     JOIN inventory t2
@@ -81,19 +135,150 @@ pub async fn top_3_rented(state: web::Data<AppState>) -> impl Responder {
     LIMIT 3
     ")
         .fetch_all(&state.db)
-        .await
-    {
-        Ok(top) => HttpResponse::Ok().json(GenericResponse::success(top, "Returned top 3 rented movies")),
-        Err(e) => {
-            println!("{e}");
-            HttpResponse::NotFound().json(GenericResponse::error((), "Didn't find any movies"))
-        }
-    }
+        .timed("top_3_rented")
+        .await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(top, "Returned top 3 rented movies")))
+}
+
+#[derive(FromRow, Serialize, Deserialize, ToSchema)]
+pub struct MovieDetails {
+    film_id: i32,
+    title: String,
+    description: Option<String>,
+    release_year: Option<i32>,
+    language_id: i16,
+    language: String,
+    original_language_id: Option<i16>,
+    original_language: Option<String>,
+    rental_duration: i16,
+    rental_rate: rust_decimal::Decimal,
+    length: Option<i16>,
+    replacement_cost: rust_decimal::Decimal,
+    rating: Option<String>,
+    special_features: Option<Vec<String>>,
+    last_update: chrono::NaiveDateTime,
+}
+
+#[derive(FromRow, Serialize, Deserialize, ToSchema)]
+pub struct MovieActor {
+    actor_id: i32,
+    first_name: String,
+    last_name: String,
+}
+
+#[derive(FromRow, Serialize, Deserialize, ToSchema)]
+pub struct MovieCategory {
+    category_id: i32,
+    name: String,
+}
+
+#[derive(FromRow, Serialize, Deserialize, ToSchema)]
+pub struct StoreAvailability {
+    store_id: i16,
+    total_copies: i64,
+    in_stock: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct MovieDetailsResponse {
+    #[serde(flatten)]
+    film: MovieDetails,
+    actors: Vec<MovieActor>,
+    categories: Vec<MovieCategory>,
+    availability: Vec<StoreAvailability>,
+}
+
+pub async fn fetch_movie_details(db: &PgPool, film_id: i32) -> Result<MovieDetailsResponse, ApiError> {
+    let film = sqlx::query_as::<_, MovieDetails>("
+    SELECT f.film_id, f.title, f.description, f.release_year::int as release_year,
+        f.language_id, trim(l.name) as language,
+        f.original_language_id, trim(ol.name) as original_language,
+        f.rental_duration, f.rental_rate, f.length, f.replacement_cost,
+        f.rating::text as rating, f.special_features, f.last_update
+    FROM film f
+    JOIN language l
+        ON l.language_id = f.language_id
+    LEFT JOIN language ol
+        ON ol.language_id = f.original_language_id
+    WHERE f.film_id = $1
+    ")
+        .bind(film_id)
+        .fetch_optional(db)
+        .timed("fetch_movie_details.film")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Movie not found"))?;
+
+    let actors = sqlx::query_as::<_, MovieActor>("
+    SELECT a.actor_id, a.first_name, a.last_name
+    FROM film_actor fa
+    JOIN actor a
+        ON a.actor_id = fa.actor_id
+    WHERE fa.film_id = $1
+    ORDER BY a.last_name, a.first_name
+    ")
+        .bind(film_id)
+        .fetch_all(db)
+        .timed("fetch_movie_details.actors")
+        .await?;
+
+    let categories = sqlx::query_as::<_, MovieCategory>("
+    SELECT c.category_id, c.name
+    FROM film_category fc
+    JOIN category c
+        ON c.category_id = fc.category_id
+    WHERE fc.film_id = $1
+    ORDER BY c.name
+    ")
+        .bind(film_id)
+        .fetch_all(db)
+        .timed("fetch_movie_details.categories")
+        .await?;
+
+    // A copy is in stock unless it has a rental without a return_date.
+    let availability = sqlx::query_as::<_, StoreAvailability>("
+    SELECT i.store_id, count(*) as total_copies,
+        count(*) FILTER (WHERE NOT EXISTS (
+            SELECT 1 FROM rental r
+            WHERE r.inventory_id = i.inventory_id
+            AND r.return_date IS NULL
+        )) as in_stock
+    FROM inventory i
+    WHERE i.film_id = $1
+    AND i.retired_at IS NULL
+    GROUP BY i.store_id
+    ORDER BY i.store_id
+    ")
+        .bind(film_id)
+        .fetch_all(db)
+        .timed("fetch_movie_details.stock")
+        .await?;
+
+    Ok(MovieDetailsResponse { film, actors, categories, availability })
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The movie with its cast, categories and availability per store", body = GenericResponse<MovieDetailsResponse, String>),
+        (status = 404, description = "Movie not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/{film_id:\\d+}")]
+pub async fn get_movie_details(state: web::Data<AppState>, path: web::Path<i32>) -> Result<HttpResponse, ApiError> {
+    let details = fetch_movie_details(&state.db, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(details, "Returned movie details")))
 }
 
-pub fn routes(cfg: &mut web::ServiceConfig) {
-    cfg
-        .service(get_all_movies)
-        .service(get_total_movies_per_category)
-        .service(top_3_rented);
-}
\ No newline at end of file
+documented_routes!(routes, MoviesApi, [
+    search::search_movies,
+    get_all_movies,
+    get_total_movies_per_category,
+    top_3_rented,
+    get_movie_details,
+    catalogue::create_movie,
+    catalogue::update_movie,
+    catalogue::delete_movie,
+    catalogue::attach_actor,
+    catalogue::detach_actor,
+    catalogue::attach_category,
+    catalogue::detach_category,
+]);
\ No newline at end of file
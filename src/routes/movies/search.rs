@@ -0,0 +1,168 @@
+use crate::AppState;
+use crate::metrics::Timed;
+use crate::models::{escape_like, ApiError, GenericResponse, PageParams, PageQuery, StepDetails};
+
+use actix_web::{get, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::{self, FromRow, Postgres, QueryBuilder};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Words, `"quoted phrases"` and `prefix*` terms; all must match.
+    pub q: String,
+    /// Category name, case-insensitive.
+    pub category: Option<String>,
+    pub rating: Option<String>,
+    pub language_id: Option<i16>,
+    pub release_year: Option<i32>,
+    pub min_length: Option<i16>,
+    pub max_length: Option<i16>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct MovieSearchResult {
+    film_id: i32,
+    title: String,
+    description: Option<String>,
+    release_year: Option<i32>,
+    language_id: i16,
+    length: Option<i16>,
+    rating: Option<String>,
+    rank: f32,
+}
+
+enum SearchTerm {
+    Word(String),
+    Prefix(String),
+    Phrase(String),
+}
+
+/// Splits `q` into `"quoted phrases"`, `prefix*` terms and plain words.
+fn parse_terms(q: &str) -> Vec<SearchTerm> {
+    let mut terms = Vec::new();
+    let mut rest = q.trim();
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            let phrase = quoted[..end].trim();
+            if !phrase.is_empty() {
+                terms.push(SearchTerm::Phrase(phrase.to_string()));
+            }
+            rest = quoted.get(end + 1..).unwrap_or("").trim_start();
+            continue;
+        }
+        let end = rest.find(|c: char| c.is_whitespace() || c == '"').unwrap_or(rest.len());
+        let word = &rest[..end];
+        match word.strip_suffix('*') {
+            Some(prefix) => {
+                // to_tsquery has its own syntax, so only pass it plain word characters.
+                let prefix: String = prefix.chars().filter(|c| c.is_alphanumeric()).collect();
+                if !prefix.is_empty() {
+                    terms.push(SearchTerm::Prefix(prefix));
+                }
+            }
+            None if !word.is_empty() => terms.push(SearchTerm::Word(word.to_string())),
+            None => {}
+        }
+        rest = rest[end..].trim_start();
+    }
+    terms
+}
+
+fn push_tsquery(qb: &mut QueryBuilder<'_, Postgres>, terms: &[SearchTerm]) {
+    qb.push("(");
+    for (i, term) in terms.iter().enumerate() {
+        if i > 0 {
+            qb.push(" && ");
+        }
+        match term {
+            SearchTerm::Word(word) => qb.push("plainto_tsquery('english', ").push_bind(word.clone()),
+            SearchTerm::Prefix(prefix) => qb.push("to_tsquery('english', ").push_bind(format!("{prefix}:*")),
+            SearchTerm::Phrase(phrase) => qb.push("phraseto_tsquery('english', ").push_bind(phrase.clone()),
+        };
+        qb.push(")");
+    }
+    qb.push(")");
+}
+
+fn push_search_from(qb: &mut QueryBuilder<'_, Postgres>, terms: &[SearchTerm], search: &SearchQuery) {
+    qb.push(" FROM film, (SELECT ");
+    push_tsquery(qb, terms);
+    qb.push(" AS query) search WHERE film.fulltext @@ search.query");
+    if let Some(category) = &search.category {
+        qb.push("
+        AND EXISTS (
+            SELECT 1 FROM film_category fc
+            JOIN category c ON c.category_id = fc.category_id
+            WHERE fc.film_id = film.film_id AND c.name ILIKE ").push_bind(escape_like(category));
+        qb.push(" ESCAPE '\\')");
+    }
+    if let Some(rating) = &search.rating {
+        qb.push(" AND film.rating::text = ").push_bind(rating.clone());
+    }
+    if let Some(language_id) = search.language_id {
+        qb.push(" AND film.language_id = ").push_bind(language_id);
+    }
+    if let Some(release_year) = search.release_year {
+        qb.push(" AND film.release_year = ").push_bind(release_year);
+    }
+    if let Some(min_length) = search.min_length {
+        qb.push(" AND film.length >= ").push_bind(min_length);
+    }
+    if let Some(max_length) = search.max_length {
+        qb.push(" AND film.length <= ").push_bind(max_length);
+    }
+}
+
+/// Full-text search over `film.fulltext`, ranked with `ts_rank`.
+/// `q` accepts plain words, `"quoted phrases"` and `prefix*` terms, all of which must match.
+#[utoipa::path(
+    params(SearchQuery, PageQuery),
+    responses(
+        (status = 200, description = "A page of matches, best first unless `sort` is given", body = GenericResponse<Vec<MovieSearchResult>, String>),
+        (status = 400, description = "No search term other than stopwords, or invalid paging or sort column", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/search")]
+pub async fn search_movies(
+    state: web::Data<AppState>,
+    page: PageParams,
+    search: web::Query<SearchQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let terms = parse_terms(&search.q);
+    if terms.is_empty() {
+        return Err(ApiError::bad_request("q must contain at least one search term"));
+    }
+    // Stopwords such as "the" parse to an empty tsquery, which matches nothing.
+    let mut nodes = QueryBuilder::new("SELECT numnode");
+    push_tsquery(&mut nodes, &terms);
+    let nodes: i32 = nodes.build_query_scalar().fetch_one(&state.db).timed("search_movies.terms").await?;
+    if nodes == 0 {
+        return Err(ApiError::bad_request("q must contain at least one search term that is not a stopword"));
+    }
+
+    let mut count = QueryBuilder::new("SELECT count(*)");
+    push_search_from(&mut count, &terms, &search);
+    let total: i64 = count.build_query_scalar().fetch_one(&state.db).timed("search_movies.count").await?;
+
+    let mut query = QueryBuilder::new("
+    SELECT film.film_id, film.title, film.description, film.release_year::int as release_year,
+        film.language_id, film.length, film.rating::text as rating,
+        ts_rank(film.fulltext, search.query) as rank");
+    push_search_from(&mut query, &terms, &search);
+    match page.sort {
+        Some(_) => page.push_order_by(&mut query, &["film_id", "title", "release_year", "length", "rank"], "film_id")?,
+        None => {
+            query.push(" ORDER BY rank DESC, film_id");
+        }
+    }
+    page.push_limit(&mut query);
+    let movies = query.build_query_as::<MovieSearchResult>()
+        .fetch_all(&state.db)
+        .timed("search_movies.page")
+        .await?;
+    Ok(HttpResponse::Ok().json(
+        GenericResponse::paginated(movies, "Returned matching movies", page.pagination(total))))
+}
@@ -0,0 +1,3 @@
+pub mod payments;
+
+pub use payments::{customer_routes, routes, CustomerPaymentsApi, PaymentsApi};
\ No newline at end of file
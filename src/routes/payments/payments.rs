@@ -0,0 +1,145 @@
+use crate::AppState;
+use crate::auth::{AuthenticatedStaff, ReportReader};
+use crate::metrics::Timed;
+use crate::models::{ApiError, GenericResponse, StepDetails};
+
+use actix_web::{get, post, web, HttpResponse};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{self, FromRow};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Payment {
+    payment_id: i32,
+    customer_id: i16,
+    staff_id: i16,
+    rental_id: i32,
+    amount: Decimal,
+    payment_date: chrono::NaiveDateTime,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct PaymentForm {
+    customer_id: i16,
+    rental_id: i32,
+    amount: Decimal,
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The recorded payment, taken by the caller", body = GenericResponse<Payment, String>),
+        (status = 400, description = "Amount is not positive or the rental belongs to another customer", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not logged in as staff, or the rented copy belongs to another store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Rental not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("")]
+pub async fn create_payment(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    form: web::Json<PaymentForm>,
+) -> Result<HttpResponse, ApiError> {
+    if form.amount <= Decimal::ZERO {
+        return Err(ApiError::bad_request("Amount must be greater than zero"));
+    }
+
+    let mut tx = state.db.begin().timed("create_payment.begin").await?;
+
+    let (customer_id, store_id) = sqlx::query_as::<_, (i16, i16)>("
+    SELECT r.customer_id, i.store_id
+    FROM rental r
+    JOIN inventory i
+        ON i.inventory_id = r.inventory_id
+    WHERE r.rental_id = $1")
+        .bind(form.rental_id)
+        .fetch_optional(&mut *tx)
+        .timed("create_payment.rental")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Rental not found"))?;
+    staff.require_store(store_id)?;
+    if customer_id != form.customer_id {
+        return Err(ApiError::bad_request("Rental belongs to a different customer"));
+    }
+
+    let payment = sqlx::query_as::<_, Payment>("
+    INSERT INTO payment (customer_id, staff_id, rental_id, amount, payment_date)
+    VALUES ($1, $2, $3, $4, now())
+    RETURNING *")
+        .bind(form.customer_id)
+        .bind(staff.staff_id_i16()?)
+        .bind(form.rental_id)
+        .bind(form.amount)
+        .fetch_one(&mut *tx)
+        .timed("create_payment.insert")
+        .await?;
+
+    tx.commit().timed("create_payment.commit").await?;
+
+    Ok(HttpResponse::Ok().json(GenericResponse::success(payment, "Successfully recorded payment")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Payments for the rental, oldest first", body = GenericResponse<Vec<Payment>, String>),
+        (status = 401, description = "Neither a staff token nor an API key", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "API key is missing the reports:read scope", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/rental/{rental_id}")]
+pub async fn get_rental_payments(
+    state: web::Data<AppState>,
+    _reader: ReportReader,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let rental_id = path.into_inner();
+    let payments = sqlx::query_as::<_, Payment>("
+    SELECT * FROM payment
+    WHERE rental_id = $1
+    ORDER BY payment_date")
+        .bind(rental_id)
+        .fetch_all(&state.db)
+        .timed("get_rental_payments")
+        .await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(payments, "Returned payments for rental")))
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CustomerBalance {
+    customer_id: i32,
+    balance: Decimal,
+}
+
+/// Outstanding amount as computed by Pagila's `get_customer_balance`: rental fees,
+/// late fees and replacement cost for long-overdue films, minus payments made so far.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The customer's outstanding balance", body = GenericResponse<CustomerBalance, String>),
+        (status = 401, description = "Neither a staff token nor an API key", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "API key is missing the reports:read scope", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Customer not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/{customer_id}/balance")]
+pub async fn get_customer_balance(
+    state: web::Data<AppState>,
+    _reader: ReportReader,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let customer_id = path.into_inner();
+    let balance = sqlx::query_as::<_, CustomerBalance>("
+    SELECT customer_id, get_customer_balance(customer_id, now()::timestamp) as balance
+    FROM customer
+    WHERE customer_id = $1")
+        .bind(customer_id)
+        .fetch_optional(&state.db)
+        .timed("get_customer_balance")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Customer not found"))?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(balance, "Returned customer balance")))
+}
+
+documented_routes!(routes, PaymentsApi, [create_payment, get_rental_payments]);
+
+documented_routes!(customer_routes, CustomerPaymentsApi, [get_customer_balance]);
@@ -0,0 +1,3 @@
+pub mod rentals;
+
+pub use rentals::{routes, RentalsApi};
\ No newline at end of file
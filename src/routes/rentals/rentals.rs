@@ -0,0 +1,251 @@
+use crate::AppState;
+use crate::auth::{AuthenticatedStaff, ReportReader};
+use crate::metrics::Timed;
+use crate::models::{ApiError, GenericResponse, PageParams, PageQuery, StepDetails};
+
+use actix_web::{get, post, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use rust_decimal::Decimal;
+use sqlx::{self, FromRow, Postgres, QueryBuilder};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Rental {
+    rental_id: i32,
+    rental_date: chrono::NaiveDateTime,
+    inventory_id: i32,
+    customer_id: i16,
+    return_date: Option<chrono::NaiveDateTime>,
+    staff_id: i16,
+    /// Who checked the copy back in, `null` while it is out.
+    returned_by: Option<i16>,
+    last_update: chrono::NaiveDateTime,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct RentalForm {
+    customer_id: i16,
+    inventory_id: i32,
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The new rental, handled by the caller", body = GenericResponse<Rental, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not logged in as staff, or the copy belongs to another store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Inventory item not found", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 409, description = "The copy is already rented", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 422, description = "Unknown customer", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("")]
+pub async fn create_rental(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    form: web::Json<RentalForm>,
+) -> Result<HttpResponse, ApiError> {
+    let mut tx = state.db.begin().timed("create_rental.begin").await?;
+
+    // Lock the copy so two concurrent checkouts can't both see it on the shelf.
+    let store_id = sqlx::query_scalar::<_, i16>("
+    SELECT store_id FROM inventory
+    WHERE inventory_id = $1
+    AND retired_at IS NULL
+    FOR UPDATE
+    ")
+        .bind(form.inventory_id)
+        .fetch_optional(&mut *tx)
+        .timed("create_rental.copy")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Inventory item not found"))?;
+    staff.require_store(store_id)?;
+
+    // Same rule as Pagila's inventory_in_stock(): a copy is out while it has a rental without a return_date.
+    let in_stock = sqlx::query_scalar::<_, bool>("
+    SELECT NOT EXISTS(
+        SELECT 1 FROM rental
+        WHERE inventory_id = $1
+        AND return_date IS NULL
+    )")
+        .bind(form.inventory_id)
+        .fetch_one(&mut *tx)
+        .timed("create_rental.available")
+        .await?;
+    if !in_stock {
+        return Err(ApiError::conflict("Inventory item is already rented"));
+    }
+
+    let rental = sqlx::query_as::<_, Rental>("
+    INSERT INTO rental (rental_date, inventory_id, customer_id, staff_id)
+    VALUES (now(), $1, $2, $3)
+    RETURNING *")
+        .bind(form.inventory_id)
+        .bind(form.customer_id)
+        .bind(staff.staff_id_i16()?)
+        .fetch_one(&mut *tx)
+        .timed("create_rental.insert")
+        .await?;
+
+    tx.commit().timed("create_rental.commit").await?;
+
+    Ok(HttpResponse::Ok().json(GenericResponse::success(rental, "Successfully rented film")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The returned rental, checked in by the caller", body = GenericResponse<Rental, String>),
+        (status = 401, description = "No credentials", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not logged in as staff, or the copy belongs to another store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Rental not found", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 409, description = "Rental was already returned", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{rental_id}/return")]
+pub async fn return_rental(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let rental_id = path.into_inner();
+    let mut tx = state.db.begin().timed("return_rental.begin").await?;
+
+    let (returned, store_id) = sqlx::query_as::<_, (bool, i16)>("
+    SELECT r.return_date IS NOT NULL, i.store_id
+    FROM rental r
+    JOIN inventory i
+        ON i.inventory_id = r.inventory_id
+    WHERE r.rental_id = $1
+    FOR UPDATE OF r")
+        .bind(rental_id)
+        .fetch_optional(&mut *tx)
+        .timed("return_rental.rental")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Rental not found"))?;
+    staff.require_store(store_id)?;
+    if returned {
+        return Err(ApiError::conflict("Rental was already returned"));
+    }
+
+    let rental = sqlx::query_as::<_, Rental>("
+    UPDATE rental
+    SET return_date = now(), returned_by = $2
+    WHERE rental_id = $1
+    RETURNING *")
+        .bind(rental_id)
+        .bind(staff.staff_id_i16()?)
+        .fetch_one(&mut *tx)
+        .timed("return_rental.update")
+        .await?;
+
+    tx.commit().timed("return_rental.commit").await?;
+
+    Ok(HttpResponse::Ok().json(GenericResponse::success(rental, "Successfully returned film")))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OverdueFilter {
+    pub store_id: Option<i16>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OverdueRental {
+    rental_id: i32,
+    rental_date: chrono::NaiveDateTime,
+    due_date: chrono::NaiveDateTime,
+    days_overdue: i32,
+    late_fee: Decimal,
+    inventory_id: i32,
+    store_id: i16,
+    film_id: i32,
+    title: String,
+    customer_id: i16,
+    first_name: String,
+    last_name: String,
+    email: Option<String>,
+    phone: String,
+    address: String,
+    district: String,
+    postal_code: Option<String>,
+    city: String,
+}
+
+/// Open rentals past `rental_date + film.rental_duration`. The late fee follows
+/// `get_customer_balance`: a dollar per day overdue, or the replacement cost once
+/// the film has been out for more than twice its rental duration.
+fn push_overdue_from(qb: &mut QueryBuilder<'_, Postgres>, filter: &OverdueFilter) {
+    qb.push("
+    FROM (
+        SELECT t1.rental_id, t1.rental_date,
+            t1.rental_date + t3.rental_duration * interval '1 day' as due_date,
+            extract(day FROM now() - (t1.rental_date + t3.rental_duration * interval '1 day'))::int as days_overdue,
+            CASE
+                WHEN now() - t1.rental_date > t3.rental_duration * 2 * interval '1 day' THEN t3.replacement_cost
+                ELSE extract(day FROM now() - (t1.rental_date + t3.rental_duration * interval '1 day'))
+            END::numeric(5,2) as late_fee,
+            t1.inventory_id, t2.store_id, t3.film_id, t3.title,
+            t1.customer_id, t4.first_name, t4.last_name, t4.email,
+            t5.phone, t5.address, t5.district, t5.postal_code,
+            t6.city
+        FROM rental t1
+        JOIN inventory t2
+            ON t2.inventory_id = t1.inventory_id
+        JOIN film t3
+            ON t3.film_id = t2.film_id
+        JOIN customer t4
+            ON t4.customer_id = t1.customer_id
+        JOIN address t5
+            ON t5.address_id = t4.address_id
+        JOIN city t6
+            ON t6.city_id = t5.city_id
+        WHERE t1.return_date IS NULL
+        AND t1.rental_date + t3.rental_duration * interval '1 day' < now()
+    ) overdue
+    WHERE true");
+    if let Some(store_id) = filter.store_id {
+        qb.push(" AND store_id = ").push_bind(store_id);
+    }
+}
+
+#[utoipa::path(
+    params(OverdueFilter, PageQuery),
+    responses(
+        (status = 200, description = "A page of overdue rentals with the customer's contact details, longest overdue first unless `sort` is given", body = GenericResponse<Vec<OverdueRental>, String>),
+        (status = 400, description = "Invalid paging, filter or sort column", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 401, description = "Neither a staff token nor an API key", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "API key is missing the reports:read scope", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/overdue")]
+pub async fn get_overdue_rentals(
+    state: web::Data<AppState>,
+    _reader: ReportReader,
+    page: PageParams,
+    filter: web::Query<OverdueFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let mut count = QueryBuilder::new("SELECT count(*)");
+    push_overdue_from(&mut count, &filter);
+    let total: i64 = count.build_query_scalar().fetch_one(&state.db).timed("get_overdue_rentals.count").await?;
+
+    let mut query = QueryBuilder::new("SELECT *");
+    push_overdue_from(&mut query, &filter);
+    match page.sort {
+        Some(_) => page.push_order_by(
+            &mut query,
+            &["rental_id", "due_date", "days_overdue", "late_fee", "last_name", "store_id"],
+            "rental_id",
+        )?,
+        None => {
+            query.push(" ORDER BY due_date, rental_id");
+        }
+    }
+    page.push_limit(&mut query);
+    let rentals = query.build_query_as::<OverdueRental>()
+        .fetch_all(&state.db)
+        .timed("get_overdue_rentals.page")
+        .await?;
+    Ok(HttpResponse::Ok().json(
+        GenericResponse::paginated(rentals, "Returned overdue rentals", page.pagination(total))))
+}
+
+documented_routes!(routes, RentalsApi, [get_overdue_rentals, create_rental, return_rental]);
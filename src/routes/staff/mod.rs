@@ -0,0 +1,3 @@
+pub mod staff;
+
+pub use staff::{routes, StaffApi};
\ No newline at end of file
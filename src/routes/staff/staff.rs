@@ -0,0 +1,203 @@
+use crate::AppState;
+use crate::auth::{password, token, AuthenticatedStaff, Role};
+use crate::metrics::Timed;
+use crate::models::{ApiError, GenericResponse, StepDetails};
+
+use actix_web::{get, post, put, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::{self, FromRow};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct StaffProfile {
+    staff_id: i32,
+    first_name: String,
+    last_name: String,
+    email: Option<String>,
+    store_id: i16,
+    active: bool,
+    username: String,
+    last_update: chrono::NaiveDateTime,
+}
+
+const PROFILE_COLUMNS: &str = "staff_id, first_name, last_name, email, store_id, active, username, last_update";
+
+#[derive(Deserialize, ToSchema)]
+pub struct LoginForm {
+    username: String,
+    password: String,
+}
+
+#[derive(FromRow)]
+struct StaffCredentials {
+    staff_id: i32,
+    store_id: i16,
+    password: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LoginResponse {
+    /// Send as `Authorization: Bearer <token>`.
+    token: String,
+    /// Seconds until the token expires.
+    expires_in: i64,
+    staff: StaffProfile,
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "A token for the staff member", body = GenericResponse<LoginResponse, String>),
+        (status = 401, description = "Invalid username or password", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/login")]
+pub async fn login(state: web::Data<AppState>, form: web::Json<LoginForm>) -> Result<HttpResponse, ApiError> {
+    let invalid = || ApiError::unauthorized("Invalid username or password");
+    let credentials = sqlx::query_as::<_, StaffCredentials>("
+    SELECT staff_id, store_id, password
+    FROM staff
+    WHERE username = $1
+    AND active
+    ")
+        .bind(&form.username)
+        .fetch_optional(&state.db)
+        .timed("login.credentials")
+        .await?
+        .ok_or_else(invalid)?;
+    let stored = credentials.password.ok_or_else(invalid)?;
+    if !password::verify(&form.password, &stored) {
+        return Err(invalid());
+    }
+
+    if password::is_legacy(&stored) {
+        sqlx::query("UPDATE staff SET password = $1 WHERE staff_id = $2")
+            .bind(password::rehash(&form.password)?)
+            .bind(credentials.staff_id)
+            .execute(&state.db)
+            .timed("login.rehash")
+            .await?;
+    }
+
+    let token = token::issue(credentials.staff_id, credentials.store_id, &state.jwt_secret)?;
+    let staff = sqlx::query_as::<_, StaffProfile>(&format!("SELECT {PROFILE_COLUMNS} FROM staff WHERE staff_id = $1"))
+        .bind(credentials.staff_id)
+        .fetch_one(&state.db)
+        .timed("login.profile")
+        .await?;
+    let response = LoginResponse { token, expires_in: token::TOKEN_TTL_SECONDS, staff };
+    Ok(HttpResponse::Ok().json(GenericResponse::success(response, "Logged in")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The logged-in staff member", body = GenericResponse<StaffProfile, String>),
+        (status = 401, description = "Not logged in", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/me")]
+pub async fn get_me(state: web::Data<AppState>, staff: AuthenticatedStaff) -> Result<HttpResponse, ApiError> {
+    let profile = sqlx::query_as::<_, StaffProfile>(&format!("SELECT {PROFILE_COLUMNS} FROM staff WHERE staff_id = $1"))
+        .bind(staff.staff_id)
+        .fetch_one(&state.db)
+        .timed("get_me")
+        .await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(profile, "Returned current staff member")))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateStaffForm {
+    first_name: String,
+    last_name: String,
+    address_id: i16,
+    email: Option<String>,
+    store_id: i16,
+    username: String,
+    password: String,
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The new staff member", body = GenericResponse<StaffProfile, String>),
+        (status = 400, description = "Blank username or password too short", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 401, description = "Not logged in", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager of the store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 409, description = "Username is taken", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("")]
+pub async fn create_staff(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    form: web::Json<CreateStaffForm>,
+) -> Result<HttpResponse, ApiError> {
+    staff.require(Role::Manager)?;
+    staff.require_store(form.store_id)?;
+    if form.username.trim().is_empty() {
+        return Err(ApiError::bad_request("username must not be empty"));
+    }
+    let password_hash = password::hash(&form.password)?;
+    let profile = sqlx::query_as::<_, StaffProfile>(&format!("
+    INSERT INTO staff (first_name, last_name, address_id, email, store_id, username, password)
+    VALUES ($1, $2, $3, $4, $5, $6, $7)
+    RETURNING {PROFILE_COLUMNS}
+    "))
+        .bind(&form.first_name)
+        .bind(&form.last_name)
+        .bind(form.address_id)
+        .bind(&form.email)
+        .bind(form.store_id)
+        .bind(&form.username)
+        .bind(password_hash)
+        .fetch_one(&state.db)
+        .timed("create_staff")
+        .await?;
+    Ok(HttpResponse::Ok().json(GenericResponse::success(profile, "Successfully created staff member")))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PasswordForm {
+    password: String,
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Password updated", body = GenericResponse<utoipa::TupleUnit, String>),
+        (status = 400, description = "Password too short", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 401, description = "Not logged in", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Neither the staff member nor a manager of their store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Staff member not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[put("/{staff_id}/password")]
+pub async fn reset_password(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    path: web::Path<i32>,
+    form: web::Json<PasswordForm>,
+) -> Result<HttpResponse, ApiError> {
+    let staff_id = path.into_inner();
+    if staff_id != staff.staff_id {
+        // Managers may reset the passwords of their own store's staff.
+        staff.require(Role::Manager)?;
+        let store_id = sqlx::query_scalar::<_, i16>("SELECT store_id FROM staff WHERE staff_id = $1")
+            .bind(staff_id)
+            .fetch_optional(&state.db)
+            .timed("reset_password.store")
+            .await?
+            .ok_or_else(|| ApiError::not_found("Staff member not found"))?;
+        staff.require_store(store_id)?;
+    }
+    let updated = sqlx::query("UPDATE staff SET password = $1 WHERE staff_id = $2")
+        .bind(password::hash(&form.password)?)
+        .bind(staff_id)
+        .execute(&state.db)
+        .timed("reset_password.update")
+        .await?
+        .rows_affected();
+    if updated == 0 {
+        return Err(ApiError::not_found("Staff member not found"));
+    }
+    Ok(HttpResponse::Ok().json(GenericResponse::success((), "Password updated")))
+}
+
+documented_routes!(routes, StaffApi, [login, get_me, create_staff, reset_password]);
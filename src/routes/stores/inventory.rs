@@ -0,0 +1,308 @@
+use crate::AppState;
+use crate::auth::AuthenticatedStaff;
+use crate::metrics::Timed;
+use crate::models::{escape_like, ApiError, GenericResponse, PageParams, PageQuery, StepDetails};
+
+use actix_web::{delete, get, post, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::{self, FromRow, Postgres, QueryBuilder, Transaction};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Deserialize, Serialize, Clone, Copy, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InventoryStatus {
+    OnShelf,
+    Rented,
+    Overdue,
+}
+
+impl InventoryStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::OnShelf => "on_shelf",
+            Self::Rented => "rented",
+            Self::Overdue => "overdue",
+        }
+    }
+}
+
+/// `title` is a case-insensitive substring.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct InventoryFilter {
+    pub film_id: Option<i16>,
+    pub title: Option<String>,
+    pub status: Option<InventoryStatus>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct InventoryItem {
+    inventory_id: i32,
+    film_id: i16,
+    title: String,
+    store_id: i16,
+    #[schema(value_type = InventoryStatus)]
+    status: String,
+    rental_id: Option<i32>,
+    customer_id: Option<i16>,
+    due_date: Option<chrono::NaiveDateTime>,
+    last_update: chrono::NaiveDateTime,
+}
+
+/// Live (non-retired) copies of a store with their current rental, if any.
+fn push_inventory_from(qb: &mut QueryBuilder<'_, Postgres>, store_id: i16, filter: &InventoryFilter) {
+    qb.push("
+    FROM (
+        SELECT i.inventory_id, i.film_id, f.title, i.store_id,
+            CASE
+                WHEN r.rental_id IS NULL THEN 'on_shelf'
+                WHEN r.rental_date + f.rental_duration * interval '1 day' < now() THEN 'overdue'
+                ELSE 'rented'
+            END as status,
+            r.rental_id, r.customer_id,
+            r.rental_date + f.rental_duration * interval '1 day' as due_date,
+            i.last_update
+        FROM inventory i
+        JOIN film f
+            ON f.film_id = i.film_id
+        LEFT JOIN rental r
+            ON r.inventory_id = i.inventory_id
+            AND r.return_date IS NULL
+        WHERE i.retired_at IS NULL
+        AND i.store_id = ").push_bind(store_id);
+    qb.push("
+    ) inventory_status
+    WHERE true");
+    if let Some(film_id) = filter.film_id {
+        qb.push(" AND film_id = ").push_bind(film_id);
+    }
+    if let Some(title) = &filter.title {
+        qb.push(" AND title ILIKE ").push_bind(format!("%{}%", escape_like(title))).push(" ESCAPE '\\'");
+    }
+    if let Some(status) = filter.status {
+        qb.push(" AND status = ").push_bind(status.as_str());
+    }
+}
+
+#[utoipa::path(
+    params(InventoryFilter, PageQuery),
+    responses(
+        (status = 200, description = "A page of the store's copies with their rental status", body = GenericResponse<Vec<InventoryItem>, String>),
+        (status = 400, description = "Invalid paging, filter or sort column", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[get("/{store_id}/inventory")]
+pub async fn get_store_inventory(
+    state: web::Data<AppState>,
+    path: web::Path<i16>,
+    page: PageParams,
+    filter: web::Query<InventoryFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let store_id = path.into_inner();
+    let mut count = QueryBuilder::new("SELECT count(*)");
+    push_inventory_from(&mut count, store_id, &filter);
+    let total: i64 = count.build_query_scalar().fetch_one(&state.db).timed("get_store_inventory.count").await?;
+
+    let mut query = QueryBuilder::new("SELECT *");
+    push_inventory_from(&mut query, store_id, &filter);
+    page.push_order_by(&mut query, &["inventory_id", "film_id", "title", "status", "due_date"], "inventory_id")?;
+    page.push_limit(&mut query);
+    let items = query.build_query_as::<InventoryItem>()
+        .fetch_all(&state.db)
+        .timed("get_store_inventory.page")
+        .await?;
+    Ok(HttpResponse::Ok().json(
+        GenericResponse::paginated(items, "Returned store inventory", page.pagination(total))))
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct AddCopiesForm {
+    film_id: i16,
+    #[serde(default = "one_copy")]
+    copies: i32,
+}
+
+fn one_copy() -> i32 {
+    1
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Copy {
+    inventory_id: i32,
+    film_id: i16,
+    store_id: i16,
+    last_update: chrono::NaiveDateTime,
+}
+
+async fn lock_store(tx: &mut Transaction<'_, Postgres>, store_id: i16) -> Result<(), ApiError> {
+    sqlx::query("SELECT store_id FROM store WHERE store_id = $1 FOR SHARE")
+        .bind(store_id as i32)
+        .fetch_optional(&mut **tx)
+        .timed("lock_store")
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Store {store_id} not found")))?;
+    Ok(())
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The new copies", body = GenericResponse<Vec<Copy>, String>),
+        (status = 400, description = "copies is not between 1 and 100", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 401, description = "Not logged in", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager of the store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Store or movie not found", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{store_id}/inventory")]
+pub async fn add_copies(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    path: web::Path<i16>,
+    form: web::Json<AddCopiesForm>,
+) -> Result<HttpResponse, ApiError> {
+    let store_id = path.into_inner();
+    staff.require_store(store_id)?;
+    if !(1..=100).contains(&form.copies) {
+        return Err(ApiError::bad_request("copies must be between 1 and 100"));
+    }
+
+    let mut tx = state.db.begin().timed("add_copies.begin").await?;
+    lock_store(&mut tx, store_id).await?;
+    sqlx::query("SELECT film_id FROM film WHERE film_id = $1")
+        .bind(form.film_id as i32)
+        .fetch_optional(&mut *tx)
+        .timed("add_copies.film")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Movie not found"))?;
+
+    let copies = sqlx::query_as::<_, Copy>("
+    INSERT INTO inventory (film_id, store_id)
+    SELECT $1, $2 FROM generate_series(1, $3)
+    RETURNING inventory_id, film_id, store_id, last_update
+    ")
+        .bind(form.film_id)
+        .bind(store_id)
+        .bind(form.copies)
+        .fetch_all(&mut *tx)
+        .timed("add_copies.insert")
+        .await?;
+    tx.commit().timed("add_copies.commit").await?;
+
+    Ok(HttpResponse::Ok().json(GenericResponse::success(copies, "Successfully added copies")))
+}
+
+/// Locks a live copy of `store_id` and fails unless it is on the shelf.
+async fn lock_copy_on_shelf(
+    tx: &mut Transaction<'_, Postgres>,
+    store_id: i16,
+    inventory_id: i32,
+) -> Result<(), ApiError> {
+    sqlx::query("
+    SELECT inventory_id FROM inventory
+    WHERE inventory_id = $1
+    AND store_id = $2
+    AND retired_at IS NULL
+    FOR UPDATE
+    ")
+        .bind(inventory_id)
+        .bind(store_id)
+        .fetch_optional(&mut **tx)
+        .timed("lock_copy_on_shelf.copy")
+        .await?
+        .ok_or_else(|| ApiError::not_found("Inventory item not found in this store"))?;
+
+    let rented = sqlx::query_scalar::<_, bool>("
+    SELECT EXISTS(
+        SELECT 1 FROM rental
+        WHERE inventory_id = $1
+        AND return_date IS NULL
+    )")
+        .bind(inventory_id)
+        .fetch_one(&mut **tx)
+        .timed("lock_copy_on_shelf.rented")
+        .await?;
+    if rented {
+        return Err(ApiError::conflict("Inventory item is currently rented"));
+    }
+    Ok(())
+}
+
+/// Retires a copy. The row is kept (with `retired_at` set) so its rental history stays valid.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Copy retired", body = GenericResponse<utoipa::TupleUnit, String>),
+        (status = 401, description = "Not logged in", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager of the store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "No live copy with this id in the store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 409, description = "The copy is rented out", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[delete("/{store_id}/inventory/{inventory_id}")]
+pub async fn retire_copy(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    path: web::Path<(i16, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (store_id, inventory_id) = path.into_inner();
+    staff.require_store(store_id)?;
+    let mut tx = state.db.begin().timed("retire_copy.begin").await?;
+    lock_copy_on_shelf(&mut tx, store_id, inventory_id).await?;
+
+    sqlx::query("UPDATE inventory SET retired_at = now() WHERE inventory_id = $1")
+        .bind(inventory_id)
+        .execute(&mut *tx)
+        .timed("retire_copy.update")
+        .await?;
+    tx.commit().timed("retire_copy.commit").await?;
+
+    Ok(HttpResponse::Ok().json(GenericResponse::success((), format!("Retired inventory item {inventory_id}"))))
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct TransferForm {
+    to_store_id: i16,
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The copy in its new store", body = GenericResponse<Copy, String>),
+        (status = 400, description = "The copy is already in the target store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 401, description = "Not logged in", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 403, description = "Not a manager of the store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 404, description = "Target store not found, or no live copy with this id in the store", body = GenericResponse<Option<StepDetails>, String>),
+        (status = 409, description = "The copy is rented out", body = GenericResponse<Option<StepDetails>, String>),
+    ),
+)]
+#[post("/{store_id}/inventory/{inventory_id}/transfer")]
+pub async fn transfer_copy(
+    state: web::Data<AppState>,
+    staff: AuthenticatedStaff,
+    path: web::Path<(i16, i32)>,
+    form: web::Json<TransferForm>,
+) -> Result<HttpResponse, ApiError> {
+    let (store_id, inventory_id) = path.into_inner();
+    // Copies are sent by the store that holds them.
+    staff.require_store(store_id)?;
+    if form.to_store_id == store_id {
+        return Err(ApiError::bad_request("Inventory item is already in this store"));
+    }
+
+    let mut tx = state.db.begin().timed("transfer_copy.begin").await?;
+    lock_store(&mut tx, form.to_store_id).await?;
+    lock_copy_on_shelf(&mut tx, store_id, inventory_id).await?;
+
+    let copy = sqlx::query_as::<_, Copy>("
+    UPDATE inventory
+    SET store_id = $1
+    WHERE inventory_id = $2
+    RETURNING inventory_id, film_id, store_id, last_update
+    ")
+        .bind(form.to_store_id)
+        .bind(inventory_id)
+        .fetch_one(&mut *tx)
+        .timed("transfer_copy.update")
+        .await?;
+    tx.commit().timed("transfer_copy.commit").await?;
+
+    Ok(HttpResponse::Ok().json(GenericResponse::success(copy, "Successfully transferred inventory item")))
+}
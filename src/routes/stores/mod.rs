@@ -1,3 +1,4 @@
+pub mod inventory;
 pub mod stores;
 
-pub use stores::routes;
\ No newline at end of file
+pub use stores::{routes, StoresApi};
\ No newline at end of file
@@ -1,19 +1,27 @@
 use crate::AppState;
-use crate::models::GenericResponse;
+use crate::metrics::Timed;
+use crate::models::{ApiError, GenericResponse};
+use super::inventory;
 
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{get, web, HttpResponse};
 use serde::{Deserialize, Serialize};
 use sqlx::{self, FromRow};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, FromRow)]
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
 pub struct StoresPerCountry {
     country: String,
     count: i64
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Store count per country", body = GenericResponse<Vec<StoresPerCountry>, String>),
+    ),
+)]
 #[get("/stores_per_country")]
-pub async fn get_all_stores_per_country(state: web::Data<AppState>) -> impl Responder {
-    match sqlx::query_as::<_, StoresPerCountry>("
+pub async fn get_all_stores_per_country(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let stores = sqlx::query_as::<_, StoresPerCountry>("
     SELECT count(*) as count, ct.country
     FROM store st
     JOIN address ad on st.address_id = ad.address_id
@@ -22,18 +30,16 @@ pub async fn get_all_stores_per_country(state: web::Data<AppState>) -> impl Resp
     GROUP BY ct.country_id, ct.country
     ")
         .fetch_all(&state.db)
-        .await
-    {
-        Ok(movies) => HttpResponse::Ok().json(
-            GenericResponse::success(movies, "Returned store per country")),
-        Err(e) => {
-            println!("{e}");
-            HttpResponse::NotFound().json(GenericResponse::error((), "Didn't find any stores"))
-        }
-    }
+        .timed("get_all_stores_per_country")
+        .await?;
+    Ok(HttpResponse::Ok().json(
+        GenericResponse::success(stores, "Returned store per country")))
 }
 
-pub fn routes(cfg: &mut web::ServiceConfig) {
-    cfg
-        .service(get_all_stores_per_country);
-}
\ No newline at end of file
+documented_routes!(routes, StoresApi, [
+    get_all_stores_per_country,
+    inventory::get_store_inventory,
+    inventory::add_copies,
+    inventory::retire_copy,
+    inventory::transfer_copy,
+]);
\ No newline at end of file
@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::PgPool;
+
+/// The migrations in `migrations/`, embedded at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// The migration that creates the Pagila schema itself.
+const BASELINE_VERSION: i64 = 20230801000000;
+
+/// Applies every pending migration.
+pub async fn migrate(pool: &PgPool) -> Result<(), MigrateError> {
+    adopt_existing_schema(pool).await?;
+    MIGRATOR.run(pool).await
+}
+
+/// A database loaded from the Pagila dump already has the baseline schema but no
+/// migration history. Record the baseline as applied so only the later
+/// migrations run against it.
+async fn adopt_existing_schema(pool: &PgPool) -> Result<(), MigrateError> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied = conn.list_applied_migrations().await?;
+    if applied.iter().any(|migration| migration.version == BASELINE_VERSION) {
+        return Ok(());
+    }
+    let has_schema = sqlx::query_scalar::<_, bool>("SELECT to_regclass('public.actor') IS NOT NULL")
+        .fetch_one(&mut *conn)
+        .await?;
+    if !has_schema {
+        return Ok(());
+    }
+
+    let baseline = MIGRATOR
+        .iter()
+        .find(|migration| migration.version == BASELINE_VERSION)
+        .ok_or(MigrateError::VersionMissing(BASELINE_VERSION))?;
+    sqlx::query("
+    INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+    VALUES ($1, $2, true, $3, 0)
+    ")
+        .bind(baseline.version)
+        .bind(&*baseline.description)
+        .bind(&*baseline.checksum)
+        .execute(&mut *conn)
+        .await?;
+    tracing::info!("Existing Pagila schema found, recorded migration {BASELINE_VERSION} as applied");
+    Ok(())
+}
+
+/// Fails unless every embedded migration has been applied successfully, so the
+/// server never runs against a schema older than the code expects.
+pub async fn check(pool: &PgPool) -> Result<(), String> {
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let has_history = sqlx::query_scalar::<_, bool>("SELECT to_regclass('public._sqlx_migrations') IS NOT NULL")
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !has_history {
+        return Err("Database has no migration history; run with --migrate first".to_string());
+    }
+    if let Some(version) = conn.dirty_version().await.map_err(|e| e.to_string())? {
+        return Err(format!("Migration {version} did not finish; repair the database and rerun --migrate"));
+    }
+
+    let applied: HashSet<i64> = conn
+        .list_applied_migrations()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+    let pending: Vec<String> = MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .filter(|migration| !applied.contains(&migration.version))
+        .map(|migration| format!("{} {}", migration.version, migration.description))
+        .collect();
+    if !pending.is_empty() {
+        return Err(format!(
+            "Database schema is behind, pending migrations: {}; run with --migrate",
+            pending.join(", ")
+        ));
+    }
+    Ok(())
+}
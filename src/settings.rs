@@ -0,0 +1,153 @@
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+/// Server settings. Values come from the TOML file named by `SETTINGS_FILE`
+/// (default `settings.toml`, optional), then environment variables override
+/// them; see `.env-example` and `settings.example.toml`.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Tightens CORS and hides internal error details from responses.
+    pub production: bool,
+    pub jwt_secret: String,
+    pub server: ServerSettings,
+    pub database: DatabaseSettings,
+    pub cors: CorsSettings,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    pub host: String,
+    pub port: u16,
+    /// Defaults to the number of physical CPUs when unset.
+    pub workers: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseSettings {
+    pub url: String,
+    pub min_connections: u32,
+    pub max_connections: u32,
+    pub acquire_timeout_secs: u64,
+    pub idle_timeout_secs: u64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsSettings {
+    /// Origins allowed to call the API, e.g. `http://localhost:4200`. Outside
+    /// production an empty list allows any origin.
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            workers: None,
+        }
+    }
+}
+
+impl Default for DatabaseSettings {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            min_connections: 0,
+            max_connections: 5,
+            acquire_timeout_secs: 30,
+            idle_timeout_secs: 600,
+        }
+    }
+}
+
+impl DatabaseSettings {
+    pub fn acquire_timeout(&self) -> Duration {
+        Duration::from_secs(self.acquire_timeout_secs)
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.idle_timeout_secs)
+    }
+}
+
+impl Settings {
+    pub fn load() -> Result<Self, String> {
+        let path = std::env::var("SETTINGS_FILE").unwrap_or_else(|_| "settings.toml".to_string());
+        let mut settings = if Path::new(&path).exists() {
+            let contents = std::fs::read_to_string(&path).map_err(|e| format!("{path}: {e}"))?;
+            toml::from_str(&contents).map_err(|e| format!("{path}: {e}"))?
+        } else {
+            Settings::default()
+        };
+        settings.apply_env()?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    fn apply_env(&mut self) -> Result<(), String> {
+        override_from_env("PRODUCTION", &mut self.production)?;
+        override_from_env("JWT_SECRET", &mut self.jwt_secret)?;
+        override_from_env("HOST", &mut self.server.host)?;
+        override_from_env("PORT", &mut self.server.port)?;
+        if let Some(workers) = parse_env("WORKERS")? {
+            self.server.workers = Some(workers);
+        }
+        override_from_env("DATABASE_URL", &mut self.database.url)?;
+        override_from_env("DB_MIN_CONNECTIONS", &mut self.database.min_connections)?;
+        override_from_env("DB_MAX_CONNECTIONS", &mut self.database.max_connections)?;
+        override_from_env("DB_ACQUIRE_TIMEOUT_SECS", &mut self.database.acquire_timeout_secs)?;
+        override_from_env("DB_IDLE_TIMEOUT_SECS", &mut self.database.idle_timeout_secs)?;
+        if let Ok(origins) = std::env::var("CORS_ALLOWED_ORIGINS") {
+            self.cors.allowed_origins = origins
+                .split(',')
+                .map(|origin| origin.trim().to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect();
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.database.url.is_empty() {
+            return Err("DATABASE_URL must be set".to_string());
+        }
+        if self.jwt_secret.is_empty() {
+            return Err("JWT_SECRET must be set".to_string());
+        }
+        if self.database.max_connections == 0 || self.database.min_connections > self.database.max_connections {
+            return Err("database pool needs 0 <= min_connections <= max_connections and max_connections > 0".to_string());
+        }
+        if self.server.workers == Some(0) {
+            return Err("WORKERS must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn parse_env<T: FromStr>(name: &str) -> Result<Option<T>, String>
+where
+    T::Err: Display,
+{
+    match std::env::var(name) {
+        Ok(value) => value.trim().parse().map(Some).map_err(|e| format!("{name}: {e}")),
+        Err(_) => Ok(None),
+    }
+}
+
+fn override_from_env<T: FromStr>(name: &str, target: &mut T) -> Result<(), String>
+where
+    T::Err: Display,
+{
+    if let Some(value) = parse_env(name)? {
+        *target = value;
+    }
+    Ok(())
+}
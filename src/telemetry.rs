@@ -0,0 +1,171 @@
+use std::time::Instant;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::error::InternalError;
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::middleware::Next;
+use actix_web::Error;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Instrument, Span, Subscriber};
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+/// Taken from the request when the caller sends a usable one, generated
+/// otherwise, and echoed on every response.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Installs the JSON log subscriber on stdout. `RUST_LOG` picks the levels
+/// (default `info`); `film_rental_rust::metrics=debug` adds a line per query.
+pub fn init() {
+    subscriber(std::io::stdout).init();
+}
+
+/// One JSON object per line, carrying the fields of the spans it happened in
+/// (`request_id`, `route`, `query`, ...).
+pub fn subscriber<W>(writer: W) -> impl Subscriber + Send + Sync
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(false)
+                .with_span_list(true)
+                .with_writer(writer)
+                .with_filter(filter),
+        )
+        .with(StatementCapture.with_filter(filter_fn(|meta| {
+            meta.target() == "sqlx::query" || meta.name() == "db.query"
+        })))
+}
+
+/// App middleware opening the `request` span every other log line nests in,
+/// and logging method, route, status and latency once the response is ready.
+pub async fn trace_request(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let request_id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(generate_request_id);
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        route = req.match_pattern().as_deref().unwrap_or("unmatched"),
+        path = req.path(),
+    );
+    let header = HeaderValue::from_str(&request_id).expect("Request ids are visible ASCII");
+
+    let started = Instant::now();
+    let res = next.call(req).instrument(span.clone()).await;
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    match res {
+        Ok(mut res) => {
+            log_response(&span, res.status(), latency_ms);
+            res.headers_mut().insert(REQUEST_ID_HEADER, header);
+            Ok(res)
+        }
+        // Errors from middleware have no response yet; render it here to attach the header.
+        Err(err) => {
+            let mut response = err.error_response();
+            log_response(&span, response.status(), latency_ms);
+            response.headers_mut().insert(REQUEST_ID_HEADER, header);
+            Err(InternalError::from_response(err, response).into())
+        }
+    }
+}
+
+// Logged inside the span rather than with `parent:`, which the JSON formatter
+// leaves out of the span list.
+fn log_response(span: &Span, status: StatusCode, latency_ms: f64) {
+    span.in_scope(|| {
+        if status.is_server_error() {
+            tracing::error!(status = status.as_u16(), latency_ms, "Request failed");
+        } else {
+            tracing::info!(status = status.as_u16(), latency_ms, "Request completed");
+        }
+    });
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    (1..=128).contains(&id.len())
+        && id.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+}
+
+fn generate_request_id() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// The SQL sqlx last ran inside a `db.query` span.
+struct Statement(String);
+
+/// Keeps the statement from sqlx's own `sqlx::query` events on the enclosing
+/// `db.query` span, so a failed query can be logged with its SQL without
+/// printing every statement.
+struct StatementCapture;
+
+impl<S> Layer<S> for StatementCapture
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(query_span) = ctx
+            .event_span(event)
+            .and_then(|span| span.scope().find(|span| span.name() == "db.query"))
+        else {
+            return;
+        };
+        let mut visitor = StatementVisitor::default();
+        event.record(&mut visitor);
+        // Short statements come as the summary alone, with an empty `db.statement`.
+        let statement = if visitor.statement.trim().is_empty() { visitor.summary } else { visitor.statement };
+        // sqlx pretty-prints long statements over many lines; keep log lines greppable.
+        let statement = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+        query_span.extensions_mut().replace(Statement(statement));
+    }
+}
+
+#[derive(Default)]
+struct StatementVisitor {
+    summary: String,
+    statement: String,
+}
+
+impl Visit for StatementVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "summary" => self.summary = value.to_string(),
+            "db.statement" => self.statement = value.to_string(),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+}
+
+/// The statement captured for `span`, if sqlx ran one inside it.
+pub fn statement(span: &Span) -> Option<String> {
+    span.with_subscriber(|(id, dispatch)| {
+        let registry = dispatch.downcast_ref::<Registry>()?;
+        let span = registry.span(id)?;
+        let extensions = span.extensions();
+        extensions.get::<Statement>().map(|statement| statement.0.clone())
+    })
+    .flatten()
+}
@@ -0,0 +1,283 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+use common::{auth, call, TestDb, CLERK_1, MANAGER_1};
+
+#[actix_web::test]
+async fn lists_actors_with_pagination_and_filters() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/actors?per_page=2")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+    assert_eq!(body["pagination"]["total"], 3);
+    assert_eq!(body["pagination"]["total_pages"], 2);
+    assert!(body["pagination"]["next"].as_str().unwrap().contains("page=2"));
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/actors?last_name=wah")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["actor_id"], 2);
+    assert_eq!(body["pagination"]["total"], 1);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/actors?sort=first_name&order=desc")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["first_name"], "PENELOPE");
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/actors?sort=password")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["status"], "Error");
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/actors?page=9223372036854775807&per_page=100")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["message"], "page is too large");
+}
+
+#[actix_web::test]
+async fn gets_a_single_actor() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/actors/1")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["first_name"], "PENELOPE");
+    assert_eq!(body["last_name"], "GUINESS");
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/actors/999")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Actor not found");
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/actors/abc")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn finds_an_actor_by_full_name() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(
+        &app,
+        TestRequest::get().uri("/api/actors/actor-query?first_name=NICK&last_name=WAHLBERG"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["actor_id"], 2);
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/actors/actor-query?first_name=NICK&last_name=CAGE")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/actors/actor-query?first_name=NICK")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn lists_an_actors_films_in_a_category() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/actors/actor-film-in-category/1/3")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["titles"], json!(["ACADEMY DINOSAUR", "ADAPTATION HOLES"]));
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/actors/actor-film-in-category/1/1")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["titles"], json!([]));
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/actors/actor-film-in-category/999/1")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn creating_an_actor_needs_a_manager() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+    let actor = json!({"first_name": "GRACE", "last_name": "MOSTEL"});
+
+    let (status, _) = call(&app, TestRequest::post().uri("/api/actors").set_json(&actor)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = call(
+        &app,
+        TestRequest::post().uri("/api/actors").insert_header(auth(CLERK_1)).set_json(&actor),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/actors").insert_header(auth(MANAGER_1)).set_json(&actor),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["actor_id"], 4);
+    assert_eq!(body["data"]["first_name"], "GRACE");
+
+    let (status, _) = call(
+        &app,
+        TestRequest::post().uri("/api/actors").insert_header(auth(MANAGER_1)).set_json(json!({"first_name": "GRACE"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn updates_an_actor() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(
+        &app,
+        TestRequest::put()
+            .uri("/api/actors/3")
+            .insert_header(auth(MANAGER_1))
+            .set_json(json!({"first_name": "EDWARD", "last_name": "CHASE"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["first_name"], "EDWARD");
+
+    let (_, body) = call(&app, TestRequest::get().uri("/api/actors/3")).await;
+    assert_eq!(body["first_name"], "EDWARD");
+
+    let (status, body) = call(
+        &app,
+        TestRequest::put()
+            .uri("/api/actors/999")
+            .insert_header(auth(MANAGER_1))
+            .set_json(json!({"first_name": "EDWARD", "last_name": "CHASE"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Actor not found");
+
+    let (status, body) = call(
+        &app,
+        TestRequest::put()
+            .uri("/api/actors/3")
+            .insert_header(auth(MANAGER_1))
+            .set_json(json!({"first_name": "EDWARD", "last_name": " "})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["data"], json!({"step": "validation", "field": "last_name"}));
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post()
+            .uri("/api/actors")
+            .insert_header(auth(MANAGER_1))
+            .set_json(json!({"first_name": "A".repeat(46), "last_name": "CHASE"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["data"]["field"], "first_name");
+}
+
+#[actix_web::test]
+async fn patches_an_actor() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(
+        &app,
+        TestRequest::patch().uri("/api/actors/2").insert_header(auth(MANAGER_1)).set_json(json!({"first_name": "NICHOLAS"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["first_name"], "NICHOLAS");
+    assert_eq!(body["data"]["last_name"], "WAHLBERG");
+
+    let (status, _) = call(
+        &app,
+        TestRequest::patch().uri("/api/actors/2").insert_header(auth(CLERK_1)).set_json(json!({"first_name": "NICK"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = call(
+        &app,
+        TestRequest::patch().uri("/api/actors/2").insert_header(auth(MANAGER_1)).set_json(json!({"last_name": ""})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["data"]["field"], "last_name");
+
+    let (status, _) = call(
+        &app,
+        TestRequest::patch().uri("/api/actors/999").insert_header(auth(MANAGER_1)).set_json(json!({"first_name": "NICK"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn deletes_an_actor_without_films() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::delete().uri("/api/actors/1").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["status"], "Error");
+    assert_eq!(
+        body["data"],
+        json!([{"film_id": 1, "title": "ACADEMY DINOSAUR"}, {"film_id": 3, "title": "ADAPTATION HOLES"}])
+    );
+
+    let (_, body) = call(
+        &app,
+        TestRequest::post()
+            .uri("/api/actors")
+            .insert_header(auth(MANAGER_1))
+            .set_json(json!({"first_name": "GRACE", "last_name": "MOSTEL"})),
+    )
+    .await;
+    let actor_id = body["data"]["actor_id"].as_i64().unwrap();
+
+    let (status, body) = call(
+        &app,
+        TestRequest::delete().uri(&format!("/api/actors/{actor_id}")).insert_header(auth(MANAGER_1)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!([]));
+
+    let (status, _) = call(&app, TestRequest::get().uri(&format!("/api/actors/{actor_id}"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = call(
+        &app,
+        TestRequest::delete().uri(&format!("/api/actors/{actor_id}")).insert_header(auth(MANAGER_1)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Actor not found");
+}
+
+#[actix_web::test]
+async fn cascades_an_actor_delete_when_asked() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(
+        &app,
+        TestRequest::delete().uri("/api/actors/1?cascade=true").insert_header(auth(MANAGER_1)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+    assert_eq!(body["data"][0], json!({"film_id": 1, "title": "ACADEMY DINOSAUR"}));
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/actors/1")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let cast: i64 = sqlx::query_scalar("SELECT count(*) FROM film_actor WHERE actor_id = 1")
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(cast, 0);
+    let films: i64 = sqlx::query_scalar("SELECT count(*) FROM film").fetch_one(&db.pool).await.unwrap();
+    assert_eq!(films, 3);
+}
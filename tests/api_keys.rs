@@ -0,0 +1,59 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+use common::{auth, call, TestDb, CLERK_1, MANAGER_1};
+
+fn create_key(scope: &str) -> TestRequest {
+    TestRequest::post()
+        .uri("/api/api-keys")
+        .insert_header(auth(MANAGER_1))
+        .set_json(json!({"name": "reporting job", "scopes": [scope]}))
+}
+
+#[actix_web::test]
+async fn reports_need_staff_or_a_reports_key() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+    let (_, body) = call(&app, create_key("catalogue:write")).await;
+    let catalogue_key = body["data"]["key"].as_str().unwrap().to_string();
+    let (_, body) = call(&app, create_key("reports:read")).await;
+    let reports_key = body["data"]["key"].as_str().unwrap().to_string();
+    let reports_key_id = body["data"]["api_key_id"].as_i64().unwrap();
+
+    let report_uris = [
+        "/api/rentals/overdue",
+        "/api/customers/2/balance",
+        "/api/customers/2/rentals",
+        "/api/customers/2/loans",
+        "/api/payments/rental/1",
+    ];
+    for uri in report_uris {
+        let (status, _) = call(&app, TestRequest::get().uri(uri)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{uri}");
+
+        let (status, _) = call(&app, TestRequest::get().uri(uri).insert_header(("X-Api-Key", catalogue_key.as_str()))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{uri}");
+
+        let (status, _) = call(&app, TestRequest::get().uri(uri).insert_header(("X-Api-Key", reports_key.as_str()))).await;
+        assert_eq!(status, StatusCode::OK, "{uri}");
+
+        let (status, _) = call(&app, TestRequest::get().uri(uri).insert_header(auth(CLERK_1))).await;
+        assert_eq!(status, StatusCode::OK, "{uri}");
+    }
+
+    let (status, _) = call(
+        &app,
+        TestRequest::delete().uri(&format!("/api/api-keys/{reports_key_id}")).insert_header(auth(MANAGER_1)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = call(
+        &app,
+        TestRequest::get().uri("/api/rentals/overdue").insert_header(("X-Api-Key", reports_key.as_str())),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
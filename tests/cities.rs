@@ -0,0 +1,66 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+use common::{call, TestDb};
+
+#[actix_web::test]
+async fn lists_cities_with_pagination_and_filters() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/cities")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["pagination"]["total"], 2);
+    assert_eq!(body["data"][0]["city"], "Lethbridge");
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/cities?city=wood")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["city_id"], 2);
+    assert_eq!(body["pagination"]["total"], 1);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/cities?sort=city&order=desc&per_page=1")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["city"], "Woodridge");
+    assert!(body["pagination"]["next"].is_string());
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/cities?sort=population")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/cities?page=abc")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn lists_cities_of_a_country() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/cities/2")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["city"], "Woodridge");
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/cities/99")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!([]));
+    assert_eq!(body["pagination"]["total"], 0);
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/cities/canada")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn rejects_anonymous_writes() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, _) = call(
+        &app,
+        TestRequest::post().uri("/api/cities").set_json(json!({"city": "Calgary", "country_id": 1})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
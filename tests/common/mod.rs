@@ -0,0 +1,141 @@
+//! Shared harness for the integration tests: a throwaway database per test on a
+//! local Postgres, and the same `App` that `main.rs` serves.
+//!
+//! Set `TEST_DATABASE_URL` to a role that may create databases (default
+//! `postgres://postgres@127.0.0.1/postgres`).
+#![allow(dead_code)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use actix_http::Request;
+use actix_web::body::{to_bytes, MessageBody};
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use actix_web::{web, Error};
+use serde_json::Value;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Connection, Executor, PgConnection, PgPool};
+
+use film_rental_rust::auth::token;
+use film_rental_rust::{app, cors, schema, AppState};
+
+pub const JWT_SECRET: &str = "integration-test-secret";
+
+/// Staff members from `fixtures/pagila.sql`.
+#[derive(Clone, Copy)]
+pub struct Staff {
+    pub staff_id: i32,
+    pub store_id: i16,
+}
+
+pub const MANAGER_1: Staff = Staff { staff_id: 1, store_id: 1 };
+pub const MANAGER_2: Staff = Staff { staff_id: 2, store_id: 2 };
+pub const CLERK_1: Staff = Staff { staff_id: 3, store_id: 1 };
+
+static NEXT_DATABASE: AtomicUsize = AtomicUsize::new(0);
+
+/// A migrated and seeded database that is dropped again with the value.
+pub struct TestDb {
+    pub pool: PgPool,
+    name: String,
+    admin_options: PgConnectOptions,
+}
+
+impl TestDb {
+    pub async fn new() -> Self {
+        let admin_url = std::env::var("TEST_DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@127.0.0.1/postgres".to_string());
+        let admin_options: PgConnectOptions = admin_url.parse().expect("TEST_DATABASE_URL is not a Postgres URL");
+        let name = format!(
+            "film_rental_test_{}_{}",
+            std::process::id(),
+            NEXT_DATABASE.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let mut admin = PgConnection::connect_with(&admin_options)
+            .await
+            .expect("Cannot connect to TEST_DATABASE_URL");
+        admin
+            .execute(format!(r#"DROP DATABASE IF EXISTS "{name}" WITH (FORCE)"#).as_str())
+            .await
+            .expect("Cannot drop stale test database");
+        admin
+            .execute(format!(r#"CREATE DATABASE "{name}""#).as_str())
+            .await
+            .expect("Cannot create test database");
+        admin.close().await.ok();
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect_with(admin_options.clone().database(&name))
+            .await
+            .expect("Cannot connect to test database");
+        schema::migrate(&pool).await.expect("Migrations failed");
+        pool.execute(include_str!("../fixtures/pagila.sql"))
+            .await
+            .expect("Loading fixtures failed");
+
+        Self { pool, name, admin_options }
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        let name = self.name.clone();
+        let options = self.admin_options.clone();
+        // Drop can't await, so clean up on a separate runtime.
+        let _ = std::thread::spawn(move || {
+            actix_web::rt::System::new().block_on(async move {
+                if let Ok(mut admin) = PgConnection::connect_with(&options).await {
+                    let _ = admin
+                        .execute(format!(r#"DROP DATABASE IF EXISTS "{name}" WITH (FORCE)"#).as_str())
+                        .await;
+                }
+            })
+        })
+        .join();
+    }
+}
+
+/// The application under test, configured like a development server.
+pub async fn init(
+    db: &TestDb,
+) -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error> {
+    let app_state = web::Data::new(AppState {
+        db: db.pool.clone(),
+        jwt_secret: JWT_SECRET.to_string(),
+    });
+    test::init_service(app(app_state, cors(&[], false))).await
+}
+
+/// `Authorization` header for a staff member.
+pub fn auth(staff: Staff) -> (&'static str, String) {
+    let token = token::issue(staff.staff_id, staff.store_id, JWT_SECRET).expect("Cannot issue token");
+    ("Authorization", format!("Bearer {token}"))
+}
+
+/// Sends `req` and returns the status with the JSON body (`Null` when empty).
+/// Errors returned by middleware are rendered the way the server would.
+pub async fn call<S, B>(app: &S, req: TestRequest) -> (StatusCode, Value)
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let (status, body) = match app.call(req.to_request()).await {
+        Ok(res) => {
+            let status = res.status();
+            (status, to_bytes(res.into_body()).await.unwrap_or_else(|_| panic!("Cannot read body")))
+        }
+        Err(err) => {
+            let res = err.as_response_error().error_response();
+            (res.status(), to_bytes(res.into_body()).await.unwrap_or_else(|_| panic!("Cannot read body")))
+        }
+    };
+    if body.is_empty() {
+        return (status, Value::Null);
+    }
+    let json = serde_json::from_slice(&body)
+        .unwrap_or_else(|_| panic!("Response is not JSON: {}", String::from_utf8_lossy(&body)));
+    (status, json)
+}
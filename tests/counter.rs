@@ -0,0 +1,70 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+
+use common::{call, TestDb};
+
+#[actix_web::test]
+async fn keeps_named_counters_in_the_database() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, _) = call(&app, TestRequest::get().uri("/counter/visits")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = call(&app, TestRequest::post().uri("/counter/visits/add")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["value"], 1);
+
+    let (_, body) = call(&app, TestRequest::post().uri("/counter/visits/add/5/3")).await;
+    assert_eq!(body["data"]["value"], 16);
+    let (_, body) = call(&app, TestRequest::post().uri("/counter/visits/minus/2/3")).await;
+    assert_eq!(body["data"]["value"], 10);
+    let (_, body) = call(&app, TestRequest::post().uri("/counter/visits/minus-query?amount=4&multiplier=2")).await;
+    assert_eq!(body["data"]["value"], 2);
+    let (_, body) = call(&app, TestRequest::post().uri("/counter/visits/add-query?amount=4&multiplier=2")).await;
+    assert_eq!(body["data"]["value"], 10);
+    let (_, body) = call(&app, TestRequest::post().uri("/counter/visits/minus/3")).await;
+    assert_eq!(body["data"]["value"], 7);
+    let (_, body) = call(&app, TestRequest::post().uri("/counter/visits/minus")).await;
+    assert_eq!(body["data"]["value"], 6);
+
+    let (_, body) = call(&app, TestRequest::post().uri("/counter/rentals/add/7")).await;
+    assert_eq!(body["data"]["value"], 7);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/counter/visits")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["value"], 6);
+
+    let value: i32 = sqlx::query_scalar("SELECT value FROM counter WHERE name = 'rentals'")
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(value, 7);
+
+    let (status, _) = call(&app, TestRequest::get().uri("/counter/visits/add")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn rejects_overflowing_changes() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::post().uri("/counter/big/add/2147483647/2")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["message"], "Value out of range");
+
+    let (status, _) = call(&app, TestRequest::post().uri("/counter/big/minus/-2147483648")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, _) = call(&app, TestRequest::post().uri("/counter/big/add/2147483647")).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = call(&app, TestRequest::post().uri("/counter/big/add")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["message"], "Value out of range");
+
+    let (_, body) = call(&app, TestRequest::get().uri("/counter/big")).await;
+    assert_eq!(body["data"]["value"], 2147483647);
+}
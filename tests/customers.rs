@@ -0,0 +1,398 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::{json, Value};
+
+use common::{auth, call, TestDb, CLERK_1, MANAGER_1, MANAGER_2};
+
+fn new_customer() -> Value {
+    json!({
+        "store_id": 1,
+        "first_name": "LINDA",
+        "last_name": "WILLIAMS",
+        "email": "LINDA.WILLIAMS@sakilacustomer.org",
+        "activebool": true,
+        "address": {
+            "address": "692 Joliet Street",
+            "address2": null,
+            "district": "Attika",
+            "postal_code": "83579",
+            "phone": "448477190408",
+            "city": "Athenai",
+            "country": "Greece"
+        }
+    })
+}
+
+#[actix_web::test]
+async fn counts_customers_per_shop() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/customers/total_per_shop")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+    assert_eq!(body["data"][0]["count"], 1);
+}
+
+#[actix_web::test]
+async fn lists_customers_of_a_shop() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/customers/shop/1")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["first_name"], "MARY");
+    assert_eq!(body["pagination"]["total"], 1);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/customers/shop/1?active=false")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!([]));
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/customers/shop/2?email=patricia")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["last_name"], "JOHNSON");
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/customers/shop/1?sort=password")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn gets_customer_details() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/customers/1")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["first_name"], "MARY");
+    assert_eq!(body["data"]["city"], "Lethbridge");
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/customers/999")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Customer not found");
+}
+
+#[actix_web::test]
+async fn creates_a_customer_with_a_new_address() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/customers").insert_header(auth(CLERK_1)).set_json(new_customer()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let customer_id = body["data"]["customer_id"].as_i64().unwrap();
+    assert_eq!(customer_id, 3);
+
+    let (status, body) = call(&app, TestRequest::get().uri(&format!("/api/customers/{customer_id}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["city"], "Athenai");
+    assert_eq!(body["data"]["district"], "Attika");
+}
+
+#[actix_web::test]
+async fn rejects_invalid_customers() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, _) = call(&app, TestRequest::post().uri("/api/customers").set_json(new_customer())).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/customers").insert_header(auth(MANAGER_2)).set_json(new_customer()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["data"]["field"], "store_id");
+
+    let mut customer = new_customer();
+    customer["address"]["district"] = json!(" ");
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/customers").insert_header(auth(CLERK_1)).set_json(customer),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["data"], json!({"step": "validation", "field": "address.district"}));
+
+    let mut customer = new_customer();
+    customer["email"] = json!("mary.smith@SAKILACUSTOMER.org");
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/customers").insert_header(auth(CLERK_1)).set_json(customer),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["data"]["step"], "email");
+
+    let mut customer = new_customer();
+    customer["address"]["district"] = json!("A district name that is far too long");
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/customers").insert_header(auth(CLERK_1)).set_json(customer),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["data"]["step"], "address");
+
+    let (status, _) = call(
+        &app,
+        TestRequest::post().uri("/api/customers").insert_header(auth(CLERK_1)).set_json(json!({"store_id": 1})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn updates_a_customer() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let mut customer = new_customer();
+    customer["email"] = json!("MARY.SMITH@sakilacustomer.org");
+    let (status, body) = call(
+        &app,
+        TestRequest::put().uri("/api/customers/1").insert_header(auth(CLERK_1)).set_json(&customer),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["first_name"], "LINDA");
+    assert_eq!(body["data"]["city"], "Athenai");
+
+    let old_address: i64 = sqlx::query_scalar("SELECT count(*) FROM address WHERE address_id = 5")
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(old_address, 0);
+
+    customer["store_id"] = json!(2);
+    let (status, body) = call(
+        &app,
+        TestRequest::put().uri("/api/customers/1").insert_header(auth(CLERK_1)).set_json(&customer),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["data"]["field"], "store_id");
+    customer["store_id"] = json!(1);
+
+    customer["email"] = json!("patricia.johnson@sakilacustomer.org");
+    let (status, body) = call(
+        &app,
+        TestRequest::put().uri("/api/customers/1").insert_header(auth(CLERK_1)).set_json(&customer),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["data"]["step"], "email");
+
+    let (status, _) = call(
+        &app,
+        TestRequest::put().uri("/api/customers/2").insert_header(auth(CLERK_1)).set_json(&customer),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = call(
+        &app,
+        TestRequest::put().uri("/api/customers/999").insert_header(auth(CLERK_1)).set_json(&customer),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn deactivates_customers_without_open_rentals() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/customers/2/deactivate").insert_header(auth(MANAGER_2)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["message"], "Customer still has 1 open rental(s)");
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/customers/1/deactivate").insert_header(auth(CLERK_1)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["activebool"], false);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/customers/shop/1?active=false")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["first_name"], "MARY");
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/customers/1/activate").insert_header(auth(CLERK_1)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["activebool"], true);
+}
+
+#[actix_web::test]
+async fn erases_a_customer_but_keeps_their_payments() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+    let payments = || {
+        sqlx::query_scalar::<_, i64>("SELECT count(*) FROM payment WHERE customer_id = 1").fetch_one(&db.pool)
+    };
+    let payments_before = payments().await.unwrap();
+
+    let (status, _) = call(&app, TestRequest::post().uri("/api/customers/1/erase").insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = call(&app, TestRequest::post().uri("/api/customers/1/erase").insert_header(auth(MANAGER_2))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) =
+        call(&app, TestRequest::post().uri("/api/customers/1/erase").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["first_name"], "Erased");
+    assert_eq!(body["data"]["email"], Value::Null);
+    assert_eq!(body["data"]["address"], "");
+    assert_eq!(body["data"]["city"], "Lethbridge");
+    assert_eq!(body["data"]["activebool"], false);
+    assert!(body["data"]["erased_at"].is_string());
+    assert_eq!(payments().await.unwrap(), payments_before);
+
+    let (status, body) =
+        call(&app, TestRequest::post().uri("/api/customers/1/erase").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["message"], "Customer has been erased");
+
+    let (status, _) =
+        call(&app, TestRequest::post().uri("/api/customers/1/activate").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+#[actix_web::test]
+async fn lists_rental_history_and_loans() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+    let get = |uri: &str| TestRequest::get().uri(uri).insert_header(auth(CLERK_1));
+
+    let (status, body) = call(&app, get("/api/customers/1/rentals")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["pagination"]["total"], 1);
+    assert_eq!(body["data"][0]["title"], "ACADEMY DINOSAUR");
+    assert_eq!(body["data"][0]["store_id"], 1);
+    assert_eq!(body["data"][0]["staff_id"], 1);
+    assert_eq!(body["data"][0]["amount_paid"], "0.99");
+    assert!(body["data"][0]["return_date"].is_string());
+
+    let (status, body) = call(&app, get("/api/customers/2/rentals")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["amount_paid"], "0");
+    assert_eq!(body["data"][0]["return_date"], Value::Null);
+
+    let (status, _) = call(&app, get("/api/customers/1/rentals?sort=password")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = call(&app, get("/api/customers/1/loans")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!([]));
+
+    let (status, body) = call(&app, get("/api/customers/2/loans")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["title"], "ACE GOLDFINGER");
+    assert_eq!(body["data"][0]["due_date"], "2022-05-28T11:30:37");
+    assert_eq!(body["data"][0]["overdue"], true);
+
+    let (status, _) = call(&app, get("/api/customers/999/rentals")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = call(&app, get("/api/customers/999/loans")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn refuses_ids_that_do_not_fit_smallint_references() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+    sqlx::query("SELECT setval(pg_get_serial_sequence('country', 'country_id'), 40000)")
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/customers").insert_header(auth(CLERK_1)).set_json(new_customer()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["data"], json!({"step": "country", "field": "address.country"}));
+}
+
+#[actix_web::test]
+async fn searches_customers() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/customers/search?q=johnson")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let search = |staff, query: &str| {
+        TestRequest::get().uri(&format!("/api/customers/search?{query}")).insert_header(auth(staff))
+    };
+
+    let (status, body) = call(&app, search(MANAGER_2, "q=johnson")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["pagination"]["total"], 1);
+    assert_eq!(body["data"][0]["first_name"], "PATRICIA");
+    assert_eq!(body["data"][0]["city"], "Woodridge");
+    assert!(body["data"][0]["rank"].as_f64().unwrap() > 0.0);
+
+    let (status, body) = call(&app, search(MANAGER_2, "q=jonson")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["customer_id"], 2);
+
+    let (status, body) = call(&app, search(CLERK_1, "q=johnson")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!([]));
+
+    let (status, body) = call(&app, search(CLERK_1, "q=sakilacustomer")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["pagination"]["total"], 1);
+    assert_eq!(body["data"][0]["first_name"], "MARY");
+
+    let (status, body) = call(&app, search(MANAGER_1, "q=sakilacustomer&store_id=2")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["pagination"]["total"], 1);
+    assert_eq!(body["data"][0]["first_name"], "PATRICIA");
+
+    let (status, _) = call(&app, search(CLERK_1, "q=sakilacustomer&store_id=2")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = call(&app, search(CLERK_1, "q=2830338")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["first_name"], "MARY");
+
+    let (status, body) = call(&app, search(MANAGER_2, "q=17886")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["first_name"], "PATRICIA");
+
+    let (status, _) = call(&app, search(CLERK_1, "q=%20")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = call(&app, search(CLERK_1, "q=a")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["message"], "q must be at least 3 characters");
+
+    let (status, body) = call(&app, search(CLERK_1, "q=%25%25%25")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!([]));
+
+    let (status, _) =
+        call(&app, TestRequest::post().uri("/api/customers/1/erase").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = call(&app, search(CLERK_1, "q=erased")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!([]));
+}
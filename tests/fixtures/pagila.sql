@@ -0,0 +1,65 @@
+-- Small Pagila fixture loaded into every test database. Staff passwords are
+-- the sample data's legacy sha1 of "12345"; Mike and Jon manage stores 1 and 2,
+-- Sam is a clerk at store 1.
+BEGIN;
+INSERT INTO language (language_id, name) VALUES (1, 'English'), (2, 'Italian');
+
+INSERT INTO country (country_id, country) VALUES (1, 'Canada'), (2, 'Australia');
+INSERT INTO city (city_id, city, country_id) VALUES (1, 'Lethbridge', 1), (2, 'Woodridge', 2);
+INSERT INTO address (address_id, address, district, city_id, postal_code, phone) VALUES
+    (1, '47 MySakila Drive', 'Alberta', 1, NULL, '14033335568'),
+    (2, '28 MySQL Boulevard', 'QLD', 2, NULL, '6172235589'),
+    (3, '23 Workhaven Lane', 'Alberta', 1, '35200', '14033335568'),
+    (4, '1411 Lillydale Drive', 'QLD', 2, '85629', '6172235589'),
+    (5, '1913 Hanoi Way', 'Alberta', 1, '35200', '28303384290'),
+    (6, '1121 Loja Avenue', 'QLD', 2, '17886', '838635286649');
+
+INSERT INTO staff (staff_id, first_name, last_name, address_id, email, store_id, username, password) VALUES
+    (1, 'Mike', 'Hillyer', 3, 'Mike.Hillyer@sakilastaff.com', 1, 'Mike', '8cb2237d0679ca88db6464eac60da96345513964'),
+    (2, 'Jon', 'Stephens', 4, 'Jon.Stephens@sakilastaff.com', 2, 'Jon', '8cb2237d0679ca88db6464eac60da96345513964'),
+    (3, 'Sam', 'Clerk', 3, 'Sam.Clerk@sakilastaff.com', 1, 'Sam', '8cb2237d0679ca88db6464eac60da96345513964');
+INSERT INTO store (store_id, manager_staff_id, address_id) VALUES (1, 1, 1), (2, 2, 2);
+
+INSERT INTO customer (customer_id, store_id, first_name, last_name, email, address_id, activebool, create_date, active) VALUES
+    (1, 1, 'MARY', 'SMITH', 'MARY.SMITH@sakilacustomer.org', 5, true, '2022-02-14', 1),
+    (2, 2, 'PATRICIA', 'JOHNSON', 'PATRICIA.JOHNSON@sakilacustomer.org', 6, true, '2022-02-14', 1);
+
+INSERT INTO category (category_id, name) VALUES (1, 'Action'), (2, 'Comedy'), (3, 'Documentary');
+
+INSERT INTO actor (actor_id, first_name, last_name) VALUES
+    (1, 'PENELOPE', 'GUINESS'),
+    (2, 'NICK', 'WAHLBERG'),
+    (3, 'ED', 'CHASE');
+
+INSERT INTO film (film_id, title, description, release_year, language_id, rental_duration, rental_rate, length, replacement_cost, rating, special_features) VALUES
+    (1, 'ACADEMY DINOSAUR', 'A Epic Drama of a Feminist And a Mad Scientist who must Battle a Teacher in The Canadian Rockies', 2022, 1, 6, 0.99, 86, 20.99, 'PG', '{"Deleted Scenes","Behind the Scenes"}'),
+    (2, 'ACE GOLDFINGER', 'A Astounding Epistle of a Database Administrator And a Explorer who must Find a Car in Ancient China', 2022, 1, 3, 4.99, 48, 12.99, 'G', '{Trailers,"Deleted Scenes"}'),
+    (3, 'ADAPTATION HOLES', 'A Astounding Reflection of a Lumberjack And a Car who must Sink a Lumberjack in A Baloon Factory', 2022, 1, 7, 2.99, 50, 18.99, 'NC-17', '{Trailers,"Deleted Scenes"}');
+
+INSERT INTO film_actor (actor_id, film_id) VALUES (1, 1), (1, 3), (2, 2), (3, 1);
+INSERT INTO film_category (film_id, category_id) VALUES (1, 3), (2, 1), (3, 3);
+
+INSERT INTO inventory (inventory_id, film_id, store_id) VALUES
+    (1, 1, 1), (2, 1, 1), (3, 1, 2), (4, 2, 2), (5, 3, 1);
+
+INSERT INTO rental (rental_id, rental_date, inventory_id, customer_id, return_date, staff_id) VALUES
+    (1, '2022-05-24 22:53:30', 1, 1, '2022-05-26 22:04:30', 1),
+    (2, '2022-05-25 11:30:37', 4, 2, NULL, 2);
+
+INSERT INTO payment (payment_id, customer_id, staff_id, rental_id, amount, payment_date) VALUES
+    (1, 1, 1, 1, 0.99, '2022-05-25 11:30:37');
+
+SELECT setval(pg_get_serial_sequence('language', 'language_id'), 2);
+SELECT setval(pg_get_serial_sequence('country', 'country_id'), 2);
+SELECT setval(pg_get_serial_sequence('city', 'city_id'), 2);
+SELECT setval(pg_get_serial_sequence('address', 'address_id'), 6);
+SELECT setval(pg_get_serial_sequence('staff', 'staff_id'), 3);
+SELECT setval(pg_get_serial_sequence('store', 'store_id'), 2);
+SELECT setval(pg_get_serial_sequence('customer', 'customer_id'), 2);
+SELECT setval(pg_get_serial_sequence('category', 'category_id'), 3);
+SELECT setval(pg_get_serial_sequence('actor', 'actor_id'), 3);
+SELECT setval(pg_get_serial_sequence('film', 'film_id'), 3);
+SELECT setval(pg_get_serial_sequence('inventory', 'inventory_id'), 5);
+SELECT setval(pg_get_serial_sequence('rental', 'rental_id'), 2);
+SELECT setval(pg_get_serial_sequence('payment', 'payment_id'), 1);
+COMMIT;
@@ -0,0 +1,46 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+
+use common::{auth, call, TestDb, CLERK_1, MANAGER_1};
+
+#[actix_web::test]
+async fn reports_health_and_readiness() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/health")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "Success");
+
+    let (status, _) = call(&app, TestRequest::get().uri("/ready")).await;
+    assert_eq!(status, StatusCode::OK);
+
+    db.pool.close().await;
+    let (status, body) = call(&app, TestRequest::get().uri("/ready")).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "Error");
+
+    let (status, _) = call(&app, TestRequest::get().uri("/health")).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn shows_diagnostics_to_managers() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/diagnostics")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/diagnostics").insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/diagnostics").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["pool"]["max_connections"], 5);
+    assert!(body["data"]["pool"]["size"].as_u64().unwrap() >= 1);
+    assert!(body["data"]["server_version"].as_str().unwrap().starts_with("PostgreSQL"));
+    assert!(body["data"]["acquire_wait_ms"].is_f64());
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+
+use common::{call, TestDb};
+
+#[actix_web::test]
+async fn exports_route_and_query_metrics() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    call(&app, TestRequest::get().uri("/api/actors/1")).await;
+    call(&app, TestRequest::get().uri("/api/actors/999")).await;
+    call(&app, TestRequest::get().uri("/no/such/route")).await;
+
+    let res = test::call_service(&app, TestRequest::get().uri("/metrics").to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+
+    for expected in [
+        r#"http_requests_total{method="GET",route="/api/actors/{id}",status="200"}"#,
+        r#"http_requests_total{method="GET",route="/api/actors/{id}",status="404"}"#,
+        r#"http_requests_total{method="GET",route="unmatched",status="404"}"#,
+        r#"http_request_duration_seconds_bucket{method="GET",route="/api/actors/{id}",le="0.005"}"#,
+        r#"db_query_duration_seconds_count{query="get_actor"}"#,
+        r#"db_pool_connections{state="in_use"}"#,
+        "db_pool_max_connections 5",
+    ] {
+        assert!(body.contains(expected), "missing {expected} in:\n{body}");
+    }
+    assert!(!body.contains("/api/actors/999"));
+}
@@ -0,0 +1,333 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::{json, Value};
+
+use common::{auth, call, TestDb, CLERK_1, MANAGER_1};
+
+fn new_movie() -> Value {
+    json!({
+        "title": "AFRICAN EGG",
+        "description": "A Fast-Paced Documentary of a Pastry Chef And a Dentist who must Pursue a Forensic Psychologist in The Gulf of Mexico",
+        "release_year": 2022,
+        "language_id": 1,
+        "original_language_id": 2,
+        "rental_duration": 6,
+        "rental_rate": "2.99",
+        "length": 130,
+        "replacement_cost": "22.99",
+        "rating": "G",
+        "special_features": ["Deleted Scenes"],
+        "actor_ids": [2, 3],
+        "category_ids": [2]
+    })
+}
+
+#[actix_web::test]
+async fn lists_movies_with_pagination_and_filters() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies?per_page=2&page=2")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["title"], "ADAPTATION HOLES");
+    assert_eq!(body["pagination"]["total"], 3);
+    assert!(body["pagination"]["prev"].is_string());
+    assert!(body["pagination"]["next"].is_null());
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies?rating=G")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["title"], "ACE GOLDFINGER");
+    assert_eq!(body["pagination"]["total"], 1);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies?sort=replacement_cost&order=desc")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["film_id"], 1);
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/movies?sort=rental_rate")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn reports_totals_and_top_rentals() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies/total_by_category")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0], json!({"category_name": "Documentary", "count": 2}));
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies/top_3_rented")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+}
+
+#[actix_web::test]
+async fn gets_movie_details() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies/1")).await;
+    assert_eq!(status, StatusCode::OK);
+    let movie = &body["data"];
+    assert_eq!(movie["title"], "ACADEMY DINOSAUR");
+    assert_eq!(movie["language"], "English");
+    assert_eq!(movie["actors"].as_array().unwrap().len(), 2);
+    assert_eq!(movie["categories"], json!([{"category_id": 3, "name": "Documentary"}]));
+    assert_eq!(
+        movie["availability"],
+        json!([
+            {"store_id": 1, "total_copies": 2, "in_stock": 2},
+            {"store_id": 2, "total_copies": 1, "in_stock": 1}
+        ])
+    );
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies/2")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["availability"], json!([{"store_id": 2, "total_copies": 1, "in_stock": 0}]));
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies/999")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Movie not found");
+}
+
+#[actix_web::test]
+async fn searches_movies() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies/search?q=astounding")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["pagination"]["total"], 2);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies/search?q=%22mad%20scientist%22")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["film_id"], 1);
+    assert_eq!(body["pagination"]["total"], 1);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies/search?q=lumber*")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["title"], "ADAPTATION HOLES");
+
+    let (status, body) =
+        call(&app, TestRequest::get().uri("/api/movies/search?q=astounding&category=action&max_length=60")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["title"], "ACE GOLDFINGER");
+    assert_eq!(body["pagination"]["total"], 1);
+
+    let (status, body) =
+        call(&app, TestRequest::get().uri("/api/movies/search?q=astounding&sort=film_id&order=desc")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["film_id"], 3);
+    assert_eq!(body["data"][1]["film_id"], 2);
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/movies/search?q=%20%22%22")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies/search?q=the")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["message"], "q must contain at least one search term that is not a stopword");
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/movies/search")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn creates_a_movie_with_cast_and_categories() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, _) = call(&app, TestRequest::post().uri("/api/movies").set_json(new_movie())).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = call(
+        &app,
+        TestRequest::post().uri("/api/movies").insert_header(auth(CLERK_1)).set_json(new_movie()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/movies").insert_header(auth(MANAGER_1)).set_json(new_movie()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let movie = &body["data"];
+    assert_eq!(movie["film_id"], 4);
+    assert_eq!(movie["original_language"], "Italian");
+    assert_eq!(movie["actors"].as_array().unwrap().len(), 2);
+    assert_eq!(movie["categories"], json!([{"category_id": 2, "name": "Comedy"}]));
+    assert_eq!(movie["availability"], json!([]));
+}
+
+#[actix_web::test]
+async fn lists_movies_created_without_optional_fields() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let mut movie = new_movie();
+    movie["description"] = Value::Null;
+    movie["release_year"] = Value::Null;
+    movie["rating"] = Value::Null;
+    let (status, _) = call(
+        &app,
+        TestRequest::post().uri("/api/movies").insert_header(auth(MANAGER_1)).set_json(movie),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies?title=african")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["film_id"], 4);
+    assert_eq!(body["data"][0]["description"], Value::Null);
+    assert_eq!(body["data"][0]["rating"], Value::Null);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/movies?title=a_")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!([]));
+}
+
+#[actix_web::test]
+async fn rejects_invalid_movies() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let cases = [
+        ("rating", json!("X"), "rating"),
+        ("release_year", json!(1800), "release_year"),
+        ("special_features", json!(["Bloopers"]), "special_features"),
+        ("language_id", json!(9), "language_id"),
+        ("actor_ids", json!([1, 99]), "actor_ids"),
+        ("category_ids", json!([42]), "category_ids"),
+    ];
+    for (key, value, field) in cases {
+        let mut movie = new_movie();
+        movie[key] = value;
+        let (status, body) = call(
+            &app,
+            TestRequest::post().uri("/api/movies").insert_header(auth(MANAGER_1)).set_json(movie),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{key}");
+        assert_eq!(body["data"], json!({"step": "validation", "field": field}));
+    }
+
+    let (status, _) = call(
+        &app,
+        TestRequest::post().uri("/api/movies").insert_header(auth(MANAGER_1)).set_json(json!({"title": "NO DETAILS"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn updates_a_movie() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let mut movie = new_movie();
+    movie["title"] = json!("ACADEMY DINOSAUR II");
+    movie.as_object_mut().unwrap().remove("actor_ids");
+    let (status, body) = call(
+        &app,
+        TestRequest::put().uri("/api/movies/1").insert_header(auth(MANAGER_1)).set_json(&movie),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["title"], "ACADEMY DINOSAUR II");
+    // Without actor_ids the cast is left alone.
+    assert_eq!(body["data"]["actors"].as_array().unwrap().len(), 2);
+    assert_eq!(body["data"]["categories"], json!([{"category_id": 2, "name": "Comedy"}]));
+
+    let (status, _) = call(
+        &app,
+        TestRequest::put().uri("/api/movies/999").insert_header(auth(MANAGER_1)).set_json(&movie),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn deletes_only_movies_without_copies() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::delete().uri("/api/movies/1").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["message"], "Movie still has 3 copies in inventory");
+
+    let (_, body) = call(
+        &app,
+        TestRequest::post().uri("/api/movies").insert_header(auth(MANAGER_1)).set_json(new_movie()),
+    )
+    .await;
+    let film_id = body["data"]["film_id"].as_i64().unwrap();
+    let (status, _) = call(
+        &app,
+        TestRequest::delete().uri(&format!("/api/movies/{film_id}")).insert_header(auth(MANAGER_1)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = call(&app, TestRequest::get().uri(&format!("/api/movies/{film_id}"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = call(&app, TestRequest::delete().uri("/api/movies/999").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn assigns_and_removes_actors() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) =
+        call(&app, TestRequest::post().uri("/api/movies/2/actors/1").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["actors"].as_array().unwrap().len(), 2);
+
+    let (status, body) =
+        call(&app, TestRequest::delete().uri("/api/movies/2/actors/2").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["actors"], json!([{"actor_id": 1, "first_name": "PENELOPE", "last_name": "GUINESS"}]));
+
+    let (status, body) =
+        call(&app, TestRequest::delete().uri("/api/movies/2/actors/2").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Actor is not assigned to this movie");
+
+    let (status, body) =
+        call(&app, TestRequest::post().uri("/api/movies/2/actors/99").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Actor not found");
+
+    let (status, _) = call(&app, TestRequest::post().uri("/api/movies/99/actors/1").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn assigns_and_removes_categories() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) =
+        call(&app, TestRequest::post().uri("/api/movies/1/categories/2").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["categories"].as_array().unwrap().len(), 2);
+
+    let (status, body) =
+        call(&app, TestRequest::delete().uri("/api/movies/1/categories/3").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["categories"], json!([{"category_id": 2, "name": "Comedy"}]));
+
+    let (status, _) =
+        call(&app, TestRequest::delete().uri("/api/movies/1/categories/3").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) =
+        call(&app, TestRequest::post().uri("/api/movies/1/categories/99").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Category not found");
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use chrono::{NaiveDateTime, Utc};
+use serde_json::json;
+
+use common::{auth, call, TestDb, CLERK_1, MANAGER_1, MANAGER_2};
+
+fn rent(inventory_id: i32, customer_id: i32) -> TestRequest {
+    TestRequest::post()
+        .uri("/api/rentals")
+        .set_json(json!({"inventory_id": inventory_id, "customer_id": customer_id}))
+}
+
+#[actix_web::test]
+async fn checks_out_copies_on_the_shelf() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, _) = call(&app, rent(2, 1)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = call(&app, rent(2, 1).insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["inventory_id"], 2);
+    assert_eq!(body["data"]["customer_id"], 1);
+    assert_eq!(body["data"]["staff_id"], 3);
+    assert!(body["data"]["return_date"].is_null());
+
+    let (status, _) = call(&app, rent(2, 1).insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // Copy 4 belongs to store 2.
+    let (status, _) = call(&app, rent(4, 1).insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = call(&app, rent(999, 1).insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) =
+        call(&app, TestRequest::delete().uri("/api/stores/1/inventory/5").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = call(&app, rent(5, 1).insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = call(&app, rent(1, 999).insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_web::test]
+async fn returns_rentals_of_the_callers_store_only() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, _) = call(&app, TestRequest::post().uri("/api/rentals/2/return")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Rental 2 is of copy 4, which belongs to store 2.
+    let (status, _) = call(&app, TestRequest::post().uri("/api/rentals/2/return").insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) =
+        call(&app, TestRequest::post().uri("/api/rentals/2/return").insert_header(auth(MANAGER_2))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"]["return_date"].is_string());
+    assert_eq!(body["data"]["returned_by"], 2);
+
+    let (status, _) = call(&app, TestRequest::post().uri("/api/rentals/2/return").insert_header(auth(MANAGER_2))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, _) = call(&app, TestRequest::post().uri("/api/rentals/999/return").insert_header(auth(MANAGER_2))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn takes_payments_for_rentals_of_the_callers_store_only() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+    let payment = json!({"customer_id": 2, "rental_id": 2, "amount": "4.99"});
+
+    // ACE GOLDFINGER's 4.99 rental rate plus its 12.99 replacement cost, as the
+    // copy has been out for far longer than twice its rental duration.
+    let (status, body) =
+        call(&app, TestRequest::get().uri("/api/customers/2/balance").insert_header(auth(MANAGER_2))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["balance"], "17.98");
+
+    let (status, _) = call(
+        &app,
+        TestRequest::post().uri("/api/payments").insert_header(auth(CLERK_1)).set_json(&payment),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/payments").insert_header(auth(MANAGER_2)).set_json(&payment),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["staff_id"], 2);
+    assert_eq!(body["data"]["amount"], "4.99");
+
+    let (status, body) =
+        call(&app, TestRequest::get().uri("/api/payments/rental/2").insert_header(auth(MANAGER_2))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["amount"], "4.99");
+
+    let (status, body) =
+        call(&app, TestRequest::get().uri("/api/customers/2/balance").insert_header(auth(MANAGER_2))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["balance"], "12.99");
+}
+
+#[actix_web::test]
+async fn lists_overdue_rentals_earliest_due_first() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    // ACADEMY DINOSAUR is due back after 6 days, so this copy is 3 days late:
+    // a dollar a day, as it has not been out for twice its rental duration yet.
+    let rental_id: i32 = sqlx::query_scalar(
+        "INSERT INTO rental (rental_date, inventory_id, customer_id, staff_id)
+        VALUES (localtimestamp - interval '9 days 1 hour', 2, 1, 1)
+        RETURNING rental_id",
+    )
+    .fetch_one(&db.pool)
+    .await
+    .unwrap();
+
+    let (status, body) =
+        call(&app, TestRequest::get().uri("/api/rentals/overdue").insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["pagination"]["total"], 2);
+
+    // Rental 2 has been out since 2022, long past twice ACE GOLDFINGER's 3 days,
+    // so it owes the 12.99 replacement cost.
+    let oldest = &body["data"][0];
+    assert_eq!(oldest["rental_id"], 2);
+    assert_eq!(oldest["due_date"], "2022-05-28T11:30:37");
+    assert_eq!(oldest["late_fee"], "12.99");
+    let due = NaiveDateTime::parse_from_str("2022-05-28T11:30:37", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let expected_days = (Utc::now().naive_utc() - due).num_days();
+    assert!((oldest["days_overdue"].as_i64().unwrap() - expected_days).abs() <= 1);
+
+    let newest = &body["data"][1];
+    assert_eq!(newest["rental_id"], rental_id);
+    assert_eq!(newest["days_overdue"], 3);
+    assert_eq!(newest["late_fee"], "3.00");
+
+    let (status, body) =
+        call(&app, TestRequest::get().uri("/api/rentals/overdue?store_id=1").insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["pagination"]["total"], 1);
+    assert_eq!(body["data"][0]["rental_id"], rental_id);
+}
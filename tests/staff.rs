@@ -0,0 +1,110 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+use common::{auth, call, TestDb, CLERK_1, MANAGER_1, MANAGER_2};
+
+/// The fixtures store sha1("12345") for every staff member.
+const FIXTURE_PASSWORD: &str = "12345";
+
+fn login(username: &str, password: &str) -> TestRequest {
+    TestRequest::post()
+        .uri("/api/staff/login")
+        .set_json(json!({"username": username, "password": password}))
+}
+
+async fn stored_password(db: &TestDb, staff_id: i32) -> String {
+    sqlx::query_scalar("SELECT password FROM staff WHERE staff_id = $1")
+        .bind(staff_id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap()
+}
+
+#[actix_web::test]
+async fn logs_in_and_upgrades_legacy_hashes() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, _) = call(&app, login("Mike", "wrong-password")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = call(&app, login("Nobody", FIXTURE_PASSWORD)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(!stored_password(&db, 1).await.starts_with("$argon2"));
+
+    let (status, body) = call(&app, login("Mike", FIXTURE_PASSWORD)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["staff"]["staff_id"], 1);
+    let token = body["data"]["token"].as_str().unwrap().to_string();
+    assert!(stored_password(&db, 1).await.starts_with("$argon2id$"));
+
+    let (status, body) = call(
+        &app,
+        TestRequest::get().uri("/api/staff/me").insert_header(("Authorization", format!("Bearer {token}"))),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["username"], "Mike");
+
+    let (status, _) = call(&app, login("Mike", FIXTURE_PASSWORD)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = call(&app, login("Mike", "wrong-password")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn rejects_logins_against_malformed_hashes() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    for stored in ["", "not-a-hash", "8cb2237d0679ca88db6464eac60da963455139"] {
+        sqlx::query("UPDATE staff SET password = $1 WHERE staff_id = 3")
+            .bind(stored)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let (status, _) = call(&app, login("Sam", FIXTURE_PASSWORD)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{stored:?}");
+        let (status, _) = call(&app, login("Sam", "")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{stored:?}");
+    }
+}
+
+#[actix_web::test]
+async fn resets_passwords_of_self_or_own_store_staff() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+    let reset = |staff_id: i32, password: &str| {
+        TestRequest::put()
+            .uri(&format!("/api/staff/{staff_id}/password"))
+            .set_json(json!({"password": password}))
+    };
+
+    let (status, _) = call(&app, reset(3, "new-password")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = call(&app, reset(3, "short").insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = call(&app, reset(3, "new-password").insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(stored_password(&db, 3).await.starts_with("$argon2id$"));
+    let (status, _) = call(&app, login("Sam", "new-password")).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = call(&app, login("Sam", FIXTURE_PASSWORD)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = call(&app, reset(1, "new-password").insert_header(auth(CLERK_1))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = call(&app, reset(3, "other-password").insert_header(auth(MANAGER_2))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = call(&app, reset(999, "other-password").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = call(&app, reset(3, "other-password").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = call(&app, login("Sam", "other-password")).await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,174 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+use common::{auth, call, TestDb, CLERK_1, MANAGER_1, MANAGER_2};
+
+#[actix_web::test]
+async fn counts_stores_per_country() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/stores/stores_per_country")).await;
+    assert_eq!(status, StatusCode::OK);
+    let mut countries: Vec<&str> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|row| row["country"].as_str().unwrap())
+        .collect();
+    countries.sort();
+    assert_eq!(countries, ["Australia", "Canada"]);
+}
+
+#[actix_web::test]
+async fn lists_store_inventory_with_status() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/stores/1/inventory")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["pagination"]["total"], 3);
+    assert_eq!(body["data"][0]["status"], "on_shelf");
+
+    // Rental 2 of copy 4 was never returned.
+    let (status, body) = call(&app, TestRequest::get().uri("/api/stores/2/inventory?status=overdue")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["inventory_id"], 4);
+    assert_eq!(body["data"][0]["rental_id"], 2);
+    assert_eq!(body["pagination"]["total"], 1);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/stores/1/inventory?title=adaptation")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["inventory_id"], 5);
+
+    let (status, _) = call(&app, TestRequest::get().uri("/api/stores/1/inventory?status=lost")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn adds_copies() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+    let copies = json!({"film_id": 2, "copies": 3});
+
+    let (status, _) = call(
+        &app,
+        TestRequest::post().uri("/api/stores/1/inventory").insert_header(auth(CLERK_1)).set_json(&copies),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = call(
+        &app,
+        TestRequest::post().uri("/api/stores/1/inventory").insert_header(auth(MANAGER_2)).set_json(&copies),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post().uri("/api/stores/1/inventory").insert_header(auth(MANAGER_1)).set_json(&copies),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 3);
+    assert_eq!(body["data"][0]["store_id"], 1);
+
+    let (status, _) = call(
+        &app,
+        TestRequest::post()
+            .uri("/api/stores/1/inventory")
+            .insert_header(auth(MANAGER_1))
+            .set_json(json!({"film_id": 2, "copies": 0})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post()
+            .uri("/api/stores/1/inventory")
+            .insert_header(auth(MANAGER_1))
+            .set_json(json!({"film_id": 99})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Movie not found");
+}
+
+#[actix_web::test]
+async fn retires_copies_on_the_shelf() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, _) =
+        call(&app, TestRequest::delete().uri("/api/stores/1/inventory/2").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = call(&app, TestRequest::get().uri("/api/stores/1/inventory")).await;
+    assert_eq!(body["pagination"]["total"], 2);
+
+    let (status, _) =
+        call(&app, TestRequest::delete().uri("/api/stores/1/inventory/2").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) =
+        call(&app, TestRequest::delete().uri("/api/stores/2/inventory/4").insert_header(auth(MANAGER_2))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["message"], "Inventory item is currently rented");
+
+    let (status, _) =
+        call(&app, TestRequest::delete().uri("/api/stores/1/inventory/4").insert_header(auth(MANAGER_1))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn transfers_copies_between_stores() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post()
+            .uri("/api/stores/1/inventory/5/transfer")
+            .insert_header(auth(MANAGER_1))
+            .set_json(json!({"to_store_id": 2})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["store_id"], 2);
+
+    let (status, _) = call(
+        &app,
+        TestRequest::post()
+            .uri("/api/stores/2/inventory/5/transfer")
+            .insert_header(auth(MANAGER_2))
+            .set_json(json!({"to_store_id": 2})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = call(
+        &app,
+        TestRequest::post()
+            .uri("/api/stores/2/inventory/5/transfer")
+            .insert_header(auth(MANAGER_2))
+            .set_json(json!({"to_store_id": 9})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Store 9 not found");
+
+    let (status, _) = call(
+        &app,
+        TestRequest::post()
+            .uri("/api/stores/2/inventory/4/transfer")
+            .insert_header(auth(MANAGER_2))
+            .set_json(json!({"to_store_id": 1})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use serde_json::{json, Value};
+
+use common::{auth, TestDb, MANAGER_1};
+use film_rental_rust::telemetry;
+
+/// Collects the JSON log lines written while it is the default subscriber.
+#[derive(Clone, Default)]
+struct Logs(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Logs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Logs {
+    fn lines(&self) -> Vec<Value> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
+
+#[actix_web::test]
+async fn propagates_request_ids() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+
+    let res = test::call_service(&app, TestRequest::get().uri("/api/actors/1").to_request()).await;
+    let generated = res.headers().get("x-request-id").unwrap().to_str().unwrap();
+    assert_eq!(generated.len(), 32);
+
+    let res = test::call_service(
+        &app,
+        TestRequest::get().uri("/api/actors/1").insert_header(("X-Request-Id", "lb-1234")).to_request(),
+    )
+    .await;
+    assert_eq!(res.headers().get("x-request-id").unwrap(), "lb-1234");
+
+    // Rejected by the authentication middleware before any handler runs.
+    let req = TestRequest::get()
+        .uri("/api/staff/me")
+        .insert_header(("Authorization", "Basic abc"))
+        .insert_header(("X-Request-Id", "lb-5678"));
+    let err = test::try_call_service(&app, req.to_request()).await.err().expect("rejected by middleware");
+    let res = err.error_response();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(res.headers().get("x-request-id").unwrap(), "lb-5678");
+}
+
+#[actix_web::test]
+async fn logs_requests_and_failed_statements() {
+    let db = TestDb::new().await;
+    let app = common::init(&db).await;
+    let logs = Logs::default();
+    let writer = logs.clone();
+    let _guard = tracing::subscriber::set_default(telemetry::subscriber(move || writer.clone()));
+
+    let res = test::call_service(
+        &app,
+        TestRequest::post()
+            .uri("/api/customers")
+            .insert_header(auth(MANAGER_1))
+            .insert_header(("X-Request-Id", "trace-me"))
+            .set_json(json!({
+                "store_id": 1,
+                "first_name": "LINDA",
+                "last_name": "WILLIAMS",
+                "email": null,
+                "activebool": true,
+                "address": {
+                    "address": "692 Joliet Street",
+                    "address2": null,
+                    "district": "x".repeat(60),
+                    "postal_code": null,
+                    "phone": "448477190408",
+                    "city": "Athenai",
+                    "country": "Greece"
+                }
+            }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let lines = logs.lines();
+    let failed = lines
+        .iter()
+        .find(|line| line["fields"]["message"].as_str().is_some_and(|m| m.starts_with("Query failed")))
+        .expect("no query failure logged");
+    assert_eq!(failed["level"], "WARN");
+    assert_eq!(failed["fields"]["error.kind"], "database");
+    assert_eq!(failed["fields"]["error.code"], "22001");
+    assert!(failed["fields"]["db.statement"].as_str().unwrap().starts_with("INSERT INTO address ("));
+    assert_eq!(failed["spans"][0]["request_id"], "trace-me");
+    assert_eq!(failed["spans"][1]["query"], "insert_address");
+
+    let completed = lines.last().unwrap();
+    assert_eq!(completed["fields"]["message"], "Request completed");
+    assert_eq!(completed["fields"]["status"], 422);
+    assert_eq!(completed["spans"][0]["route"], "/api/customers");
+    assert!(completed["fields"]["latency_ms"].is_f64());
+}